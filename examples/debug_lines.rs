@@ -0,0 +1,35 @@
+use std::process::exit;
+
+use sdl2::log::log;
+use wgpu_game::app::XAppBuilder;
+
+/// Bounds of the default scene triangles.
+const BOUNDS: ([f32; 3], [f32; 3]) = ([-0.5, -0.5, 0.0], [0.6, 0.6, 0.0]);
+/// Turns per second of the spinning line.
+const SPIN_SPEED: f32 = 0.25;
+
+fn main() {
+    let mut game = match XAppBuilder::new("WGPU Debug Lines").build() {
+        Ok(x) => x,
+        Err(e) => {
+            log(&format!("Error on init XApp: {}", e));
+            exit(1);
+        }
+    };
+
+    // lines are cleared every frame, so they are queued again each time
+    let mut angle = 0.0f32;
+    let result = game.run_with(|app, dt| {
+        angle += dt * SPIN_SPEED * std::f32::consts::TAU;
+        app.draw_box(BOUNDS.0, BOUNDS.1, [1.0, 1.0, 0.0, 1.0]);
+        app.draw_line(
+            [0.0, 0.0, 0.0],
+            [angle.cos() * 0.8, angle.sin() * 0.8, 0.0],
+            [1.0, 1.0, 1.0, 0.8],
+        );
+    });
+    if let Some(e) = result.err() {
+        log(&format!("Error on run XApp: {}", e));
+        exit(2);
+    }
+}
@@ -0,0 +1,36 @@
+use std::process::exit;
+
+use sdl2::log::log;
+use wgpu_game::app::{InstanceRaw, XApp};
+
+const GRID_SIZE: u32 = 10;
+
+fn main() {
+    let mut game = match XApp::new("WGPU Instancing") {
+        Ok(x) => x,
+        Err(e) => {
+            log(&format!("Error on init XApp: {}", e));
+            exit(1);
+        }
+    };
+
+    // lay out the default scene on a GRID_SIZE x GRID_SIZE grid covering the screen
+    let cell = 2.0 / GRID_SIZE as f32;
+    let instances = (0..GRID_SIZE)
+        .flat_map(|y| (0..GRID_SIZE).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let position = [
+                -1.0 + cell * (x as f32 + 0.5),
+                -1.0 + cell * (y as f32 + 0.5),
+                0.0,
+            ];
+            InstanceRaw::new(position, cell * 0.5)
+        })
+        .collect::<Vec<_>>();
+    game.set_instances(&instances);
+
+    if let Some(e) = game.run(&mut ()).err() {
+        log(&format!("Error on run XApp: {}", e));
+        exit(2);
+    }
+}
@@ -0,0 +1,51 @@
+use std::process::exit;
+
+use sdl2::log::log;
+use wgpu::PrimitiveTopology;
+use wgpu_game::app::{Vertex, XAppBuilder};
+
+/// Number of cells along each axis of the grid.
+const CELLS: u16 = 8;
+/// Grid cover `-EXTENT..EXTENT` on both axes.
+const EXTENT: f32 = 1.0;
+
+/// Two vertices per line, one vertical and one horizontal line per step.
+fn grid() -> (Vec<Vertex>, Vec<u16>) {
+    let mut vertices = Vec::new();
+    for i in 0..=CELLS {
+        let t = -EXTENT + 2.0 * EXTENT * i as f32 / CELLS as f32;
+        for (start, end) in [([t, -EXTENT], [t, EXTENT]), ([-EXTENT, t], [EXTENT, t])] {
+            for [x, y] in [start, end] {
+                vertices.push(Vertex {
+                    position: [x, y, 0.0],
+                    color: [0.4, 0.8, 0.4],
+                    normal: [0.0, 0.0, 1.0],
+                });
+            }
+        }
+    }
+    let indices = (0..vertices.len() as u16).collect();
+    (vertices, indices)
+}
+
+fn main() {
+    let mut game = match XAppBuilder::new("WGPU Line Grid").build() {
+        Ok(x) => x,
+        Err(e) => {
+            log(&format!("Error on init XApp: {}", e));
+            exit(1);
+        }
+    };
+
+    let (vertices, indices) = grid();
+    let id = game.add_object(&vertices, &indices);
+    if let Err(e) = game.set_topology(id, PrimitiveTopology::LineList) {
+        log(&format!("Error on set topology: {}", e));
+        exit(1);
+    }
+
+    if let Some(e) = game.run(&mut ()).err() {
+        log(&format!("Error on run XApp: {}", e));
+        exit(2);
+    }
+}
@@ -0,0 +1,32 @@
+use std::process::exit;
+
+use sdl2::log::log;
+use wgpu_game::app::{OrbitCamera, XAppBuilder};
+
+fn main() {
+    let mut game = match XAppBuilder::new("WGPU Orbit Camera").build() {
+        Ok(x) => x,
+        Err(e) => {
+            log(&format!("Error on init XApp: {}", e));
+            exit(1);
+        }
+    };
+
+    // turn slowly until the scene is dragged with the left button
+    game.set_orbit_camera(Some(OrbitCamera {
+        pitch: 0.4,
+        auto_rotate: 0.3,
+        ..OrbitCamera::default()
+    }));
+
+    let result = game.run_with(|app, _| {
+        app.draw_box([-1.0; 3], [1.0; 3], [0.6, 0.6, 0.6, 1.0]);
+        app.draw_line([0.0; 3], [1.0, 0.0, 0.0], [1.0, 0.0, 0.0, 1.0]);
+        app.draw_line([0.0; 3], [0.0, 1.0, 0.0], [0.0, 1.0, 0.0, 1.0]);
+        app.draw_line([0.0; 3], [0.0, 0.0, 1.0], [0.0, 0.0, 1.0, 1.0]);
+    });
+    if let Some(e) = result.err() {
+        log(&format!("Error on run XApp: {}", e));
+        exit(2);
+    }
+}
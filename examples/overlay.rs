@@ -0,0 +1,50 @@
+use std::process::exit;
+
+use sdl2::log::log;
+use wgpu::{Color, CompositeAlphaMode};
+use wgpu_game::app::{ScenePass, Vertex, XAppBuilder};
+
+/// Counter clockwise triangle in the middle of the window.
+fn triangle() -> (Vec<Vertex>, Vec<u16>) {
+    let corners = [
+        ([0.0, 0.5], [1.0, 0.3, 0.3]),
+        ([-0.5, -0.5], [0.3, 1.0, 0.3]),
+        ([0.5, -0.5], [0.3, 0.3, 1.0]),
+    ];
+    let vertices = corners
+        .iter()
+        .map(|&([x, y], color)| Vertex {
+            position: [x, y, 0.0],
+            color,
+            normal: [0.0, 0.0, 1.0],
+        })
+        .collect();
+    (vertices, vec![0, 1, 2])
+}
+
+fn main() {
+    let mut game = match XAppBuilder::new("WGPU Overlay")
+        .windowed(400, 400)
+        .alpha_mode(CompositeAlphaMode::PreMultiplied)
+        .build()
+    {
+        Ok(x) => x,
+        Err(e) => {
+            log(&format!("Error on init XApp: {}", e));
+            exit(1);
+        }
+    };
+    if game.surface_selection().alpha_mode != CompositeAlphaMode::PreMultiplied {
+        log("Transparent window is not supported, background stay opaque");
+    }
+
+    // premultiplied transparent black, only the triangle is visible
+    game.set_scene_pass(ScenePass::clear(Color::TRANSPARENT));
+    let (vertices, indices) = triangle();
+    game.add_object(&vertices, &indices);
+
+    if let Some(e) = game.run(&mut ()).err() {
+        log(&format!("Error on run XApp: {}", e));
+        exit(2);
+    }
+}
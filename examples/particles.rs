@@ -0,0 +1,31 @@
+use std::process::exit;
+
+use sdl2::log::log;
+use wgpu_game::app::{Game, XApp, XAppBuilder};
+
+/// Particles spawned every update, at the origin.
+const EMIT_PER_UPDATE: usize = 4;
+
+struct Fountain;
+
+impl Game for Fountain {
+    fn update(&mut self, app: &mut XApp, dt: f32) {
+        app.emit_particles(EMIT_PER_UPDATE, [0.0, 0.0]);
+        app.update_particles(dt);
+    }
+}
+
+fn main() {
+    let mut game = match XAppBuilder::new("WGPU Particles").build() {
+        Ok(x) => x,
+        Err(e) => {
+            log(&format!("Error on init XApp: {}", e));
+            exit(1);
+        }
+    };
+
+    if let Some(e) = game.run(&mut Fountain).err() {
+        log(&format!("Error on run XApp: {}", e));
+        exit(2);
+    }
+}
@@ -0,0 +1,62 @@
+use std::process::exit;
+
+use sdl2::{keyboard::Keycode, log::log};
+use wgpu_game::app::{Game, InstanceRaw, Projection, XApp};
+
+const GRID_SIZE: u32 = 8;
+
+/// Switch between orthographic and perspective camera with `C` key.
+#[derive(Default)]
+struct ProjectionToggle {
+    toggle: bool,
+}
+
+impl Game for ProjectionToggle {
+    fn render(&mut self, app: &mut XApp, _alpha: f32) {
+        if !std::mem::take(&mut self.toggle) {
+            return;
+        }
+        let projection = match app.camera().projection() {
+            Projection::Orthographic => Projection::perspective(),
+            Projection::Perspective { .. } => Projection::Orthographic,
+        };
+        log(&format!("Camera projection: {:?}", projection));
+        app.set_projection(projection);
+    }
+
+    fn key(&mut self, code: Keycode) {
+        if code == Keycode::C {
+            self.toggle = true;
+        }
+    }
+}
+
+fn main() {
+    let mut game = match XApp::new("WGPU Projection") {
+        Ok(x) => x,
+        Err(e) => {
+            log(&format!("Error on init XApp: {}", e));
+            exit(1);
+        }
+    };
+
+    // rows go further away from the camera, they only shrink in perspective
+    let cell = 2.0 / GRID_SIZE as f32;
+    let instances = (0..GRID_SIZE)
+        .flat_map(|y| (0..GRID_SIZE).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let position = [
+                -1.0 + cell * (x as f32 + 0.5),
+                -1.0 + cell * (y as f32 + 0.5),
+                y as f32 * 0.1,
+            ];
+            InstanceRaw::new(position, cell * 0.4)
+        })
+        .collect::<Vec<_>>();
+    game.set_instances(&instances);
+
+    if let Some(e) = game.run(&mut ProjectionToggle::default()).err() {
+        log(&format!("Error on run XApp: {}", e));
+        exit(2);
+    }
+}
@@ -0,0 +1,70 @@
+use std::process::exit;
+
+use sdl2::log::log;
+use wgpu::{CompareFunction, StencilFaceState, StencilOperation, StencilState};
+use wgpu_game::app::{Vertex, XAppBuilder};
+
+/// Square in the middle of the scene, only the part of the triangles
+/// inside it is drawn.
+const MASK_VERTICES: &[Vertex] = &[
+    Vertex {
+        position: [-0.25, 0.25, 0.0],
+        color: [1.0, 1.0, 1.0],
+        normal: [0.0, 0.0, 1.0],
+    },
+    Vertex {
+        position: [-0.25, -0.25, 0.0],
+        color: [1.0, 1.0, 1.0],
+        normal: [0.0, 0.0, 1.0],
+    },
+    Vertex {
+        position: [0.25, -0.25, 0.0],
+        color: [1.0, 1.0, 1.0],
+        normal: [0.0, 0.0, 1.0],
+    },
+    Vertex {
+        position: [0.25, 0.25, 0.0],
+        color: [1.0, 1.0, 1.0],
+        normal: [0.0, 0.0, 1.0],
+    },
+];
+
+const MASK_INDICES: &[u16] = &[0, 1, 2, 0, 2, 3];
+
+fn main() {
+    // draw only where the mask wrote the reference value
+    let inside_mask = StencilFaceState {
+        compare: CompareFunction::Equal,
+        fail_op: StencilOperation::Keep,
+        depth_fail_op: StencilOperation::Keep,
+        pass_op: StencilOperation::Keep,
+    };
+    let stencil = StencilState {
+        front: inside_mask,
+        back: inside_mask,
+        read_mask: 0xff,
+        write_mask: 0,
+    };
+
+    let mut game = match XAppBuilder::new("WGPU Stencil Mask")
+        .stencil(stencil)
+        .build()
+    {
+        Ok(x) => x,
+        Err(e) => {
+            log(&format!("Error on init XApp: {}", e));
+            exit(1);
+        }
+    };
+
+    let mask = game.add_object(MASK_VERTICES, MASK_INDICES);
+    if let Some(object) = game.object_mut(mask) {
+        object.stencil_mask = true;
+    }
+    game.set_stencil_reference(1);
+
+    if let Some(e) = game.run(&mut ()).err() {
+        log(&format!("Error on run XApp: {}", e));
+        exit(2);
+    }
+}
@@ -0,0 +1,39 @@
+use std::fmt;
+
+use wgpu::{AdapterInfo, Backend, DeviceType};
+
+/// GPU the app is running on.
+#[derive(Clone, Debug)]
+pub struct AdapterSummary {
+    pub backend: Backend,
+    /// Device name reported by the driver, e.g. `NVIDIA GeForce RTX 3060`.
+    pub name: String,
+    pub device_type: DeviceType,
+}
+
+impl From<&AdapterInfo> for AdapterSummary {
+    fn from(info: &AdapterInfo) -> Self {
+        AdapterSummary {
+            backend: info.backend,
+            name: info.name.clone(),
+            device_type: info.device_type,
+        }
+    }
+}
+
+/// Show as `Vulkan / NVIDIA GeForce RTX 3060`.
+impl fmt::Display for AdapterSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} / {}", self.backend, self.name)
+    }
+}
+
+/// Adapter requested with `XAppBuilder::adapter_index` or
+/// `XAppBuilder::adapter_name`.
+#[derive(Clone, Debug)]
+pub(crate) enum AdapterSelection {
+    /// Index in `XApp::enumerate_adapters`.
+    Index(usize),
+    /// Case insensitive part of the adapter name.
+    Name(String),
+}
@@ -0,0 +1,125 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use wgpu::{
+    BindGroupLayout, CommandEncoder, Device, PipelineLayout, RenderPipeline, Sampler, ShaderModule,
+    TextureFormat, TextureView,
+};
+
+use super::post::POST_SHADER;
+
+/// Copy a texture over another one with a fullscreen triangle, scaled
+/// with linear filtering when their size differ. One pipeline per target
+/// format, created on first use.
+pub(crate) struct Blitter {
+    shader: ShaderModule,
+    layout: Arc<BindGroupLayout>,
+    pipeline_layout: PipelineLayout,
+    sampler: Sampler,
+    pipelines: RefCell<HashMap<TextureFormat, RenderPipeline>>,
+}
+
+impl Blitter {
+    /// `layout` is the texture and sampler layout of `post.wgsl`.
+    pub(crate) fn new(device: &Device, layout: Arc<BindGroupLayout>) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("blit shader"),
+            source: wgpu::ShaderSource::Wgsl(POST_SHADER.into()),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("blit_pipeline_layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("blit sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        Blitter {
+            shader,
+            layout,
+            pipeline_layout,
+            sampler,
+            pipelines: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Record a pass replacing `dst`, of `dst_format`, with `src`.
+    pub(crate) fn blit(
+        &self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        src: &TextureView,
+        dst: &TextureView,
+        dst_format: TextureFormat,
+    ) {
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("blit bind group"),
+            layout: &self.layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(src),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+            ],
+        });
+        let mut pipelines = self.pipelines.borrow_mut();
+        let pipeline = pipelines
+            .entry(dst_format)
+            .or_insert_with(|| self.create_pipeline(device, dst_format));
+
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Blit Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: dst,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    // every pixel is written
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+
+    fn create_pipeline(&self, device: &Device, format: TextureFormat) -> RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("blit_pipeline"),
+            layout: Some(&self.pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &self.shader,
+                entry_point: Some("vs_main"),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &self.shader,
+                entry_point: Some("fs_main"),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        })
+    }
+}
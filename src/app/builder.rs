@@ -0,0 +1,351 @@
+use log::LevelFilter;
+use wgpu::{Backends, CompositeAlphaMode, Face, FrontFace, StencilState};
+
+use super::{adapter::AdapterSelection, texture, window_state::WindowState, XApp};
+
+/// Options used to create `XApp`.
+///
+/// ```no_run
+/// # use wgpu_game::app::XAppBuilder;
+/// let app = XAppBuilder::new("WGPU Game").compute(true).build();
+/// ```
+#[derive(Clone, Debug)]
+pub struct XAppBuilder {
+    pub(crate) title: String,
+    pub(crate) compute: bool,
+    pub(crate) wireframe: bool,
+    pub(crate) backends: Option<Backends>,
+    pub(crate) linear_format: bool,
+    pub(crate) post_process: bool,
+    pub(crate) fxaa: bool,
+    pub(crate) text: bool,
+    pub(crate) fixed_timestep: f32,
+    pub(crate) icon: Option<Vec<u8>>,
+    pub(crate) draw_geometry: bool,
+    pub(crate) debug_overlay: bool,
+    pub(crate) display: i32,
+    pub(crate) position: Option<(i32, i32)>,
+    pub(crate) windowed: Option<(u32, u32)>,
+    pub(crate) stencil: StencilState,
+    pub(crate) adapter: Option<AdapterSelection>,
+    pub(crate) max_frame_latency: u32,
+    pub(crate) target_fps: Option<u32>,
+    pub(crate) remember_window_state: bool,
+    pub(crate) cull_mode: Option<Face>,
+    pub(crate) front_face: FrontFace,
+    pub(crate) depth_prepass: bool,
+    pub(crate) alpha_mode: CompositeAlphaMode,
+    pub(crate) staging_belt: bool,
+    pub(crate) on_demand: bool,
+    pub(crate) max_anisotropy: u16,
+    pub(crate) frustum_culling: bool,
+    pub(crate) init_logger: bool,
+    pub(crate) log_level: Option<LevelFilter>,
+    pub(crate) check_winding: bool,
+}
+
+impl XAppBuilder {
+    pub fn new(title: &str) -> Self {
+        XAppBuilder {
+            title: title.to_string(),
+            compute: false,
+            wireframe: false,
+            backends: None,
+            linear_format: false,
+            post_process: false,
+            fxaa: false,
+            text: false,
+            fixed_timestep: 1.0 / 60.0,
+            icon: None,
+            draw_geometry: true,
+            debug_overlay: false,
+            display: 0,
+            position: None,
+            windowed: None,
+            stencil: StencilState::default(),
+            adapter: None,
+            max_frame_latency: 2,
+            target_fps: None,
+            remember_window_state: false,
+            cull_mode: Some(Face::Back),
+            front_face: FrontFace::Ccw,
+            depth_prepass: false,
+            alpha_mode: CompositeAlphaMode::Auto,
+            staging_belt: false,
+            on_demand: false,
+            max_anisotropy: texture::MAX_ANISOTROPY,
+            frustum_culling: true,
+            init_logger: true,
+            log_level: None,
+            check_winding: true,
+        }
+    }
+
+    /// Animate vertex positions with a compute shader. Vertex buffers get
+    /// `STORAGE` usage, ignored when the adapter can't run compute shader.
+    pub fn compute(mut self, enable: bool) -> Self {
+        self.compute = enable;
+        self
+    }
+
+    /// Start with wireframe rendering, toggled at runtime with `Tab` key.
+    pub fn wireframe(mut self, enable: bool) -> Self {
+        self.wireframe = enable;
+        self
+    }
+
+    /// Use only `backends`. Without it, the `WGPU_BACKEND` environment
+    /// variable is used, then primary backends. `Backends::SECONDARY` test
+    /// on desktop the GL path used by older Android devices.
+    pub fn backends(mut self, backends: Backends) -> Self {
+        self.backends = Some(backends);
+        self
+    }
+
+    /// Use adapter `index` of `XApp::enumerate_adapters`. When it doesn't
+    /// exist or can't present to the window, the default adapter is used.
+    pub fn adapter_index(mut self, index: usize) -> Self {
+        self.adapter = Some(AdapterSelection::Index(index));
+        self
+    }
+
+    /// Use the first adapter whose name contains `name`, ignoring case,
+    /// e.g. `"nvidia"` on a laptop with two GPUs.
+    pub fn adapter_name(mut self, name: &str) -> Self {
+        self.adapter = Some(AdapterSelection::Name(name.to_string()));
+        self
+    }
+
+    /// Prefer a linear (non sRGB) output format. The hardware doesn't
+    /// sRGB encode it, the scene shader apply gamma 2.2 instead so objects
+    /// look the same, see `XApp::gamma_correction`.
+    pub fn linear_format(mut self, enable: bool) -> Self {
+        self.linear_format = enable;
+        self
+    }
+
+    /// Draw the scene into an offscreen texture, then sample it to the
+    /// screen in a final pass.
+    pub fn post_process(mut self, enable: bool) -> Self {
+        self.post_process = enable;
+        self
+    }
+
+    /// Smooth geometry edges with FXAA in the final pass. Cheaper than
+    /// MSAA since it run once per pixel on the finished image, but also
+    /// blur a bit texture detail. Enable post process when set.
+    pub fn fxaa(mut self, enable: bool) -> Self {
+        self.fxaa = enable;
+        self
+    }
+
+    /// Enable built-in bitmap font used by `XApp::draw_text`.
+    pub fn text(mut self, enable: bool) -> Self {
+        self.text = enable;
+        self
+    }
+
+    /// Time in second between two `Game::update` call. Default to 1/60.
+    pub fn fixed_timestep(mut self, seconds: f32) -> Self {
+        self.fixed_timestep = seconds;
+        self
+    }
+
+    /// Window icon from the bytes of a BMP image, decoded by SDL. An image
+    /// that can't be decoded is logged and the default icon is kept.
+    pub fn icon(mut self, bmp: &[u8]) -> Self {
+        self.icon = Some(bmp.to_vec());
+        self
+    }
+
+    /// Draw objects and sprites. When disabled frames only clear the
+    /// screen, useful to tell surface setup bugs from pipeline bugs.
+    pub fn draw_geometry(mut self, enable: bool) -> Self {
+        self.draw_geometry = enable;
+        self
+    }
+
+    /// Allow toggling an overlay with FPS, CPU and GPU frame time, adapter
+    /// and present mode with `F3` key. It is drawn over everything else.
+    pub fn debug_overlay(mut self, enable: bool) -> Self {
+        self.debug_overlay = enable;
+        self
+    }
+
+    /// Cap the frame rate when vsync is off, i.e. present mode isn't
+    /// `Fifo`, to save power. Unlimited by default.
+    pub fn target_fps(mut self, fps: u32) -> Self {
+        self.target_fps = Some(fps);
+        self
+    }
+
+    /// Number of frames queued ahead of the GPU, clamped to `1..=3`,
+    /// default to 2. Lower value reduce input lag, the CPU wait for the GPU
+    /// sooner so throughput may drop. Higher value keep the GPU busy at the
+    /// cost of showing older frames.
+    pub fn max_frame_latency(mut self, frames: u32) -> Self {
+        self.max_frame_latency = frames;
+        self
+    }
+
+    /// Open the window on display `index`, see `sdl2::VideoSubsystem::num_video_displays`.
+    /// An index out of range fall back to display 0 with a warning.
+    pub fn display(mut self, index: i32) -> Self {
+        self.display = index;
+        self
+    }
+
+    /// Window top left corner, relative to the top left of the display.
+    /// Centered on the display by default.
+    pub fn position(mut self, x: i32, y: i32) -> Self {
+        self.position = Some((x, y));
+        self
+    }
+
+    /// Open a `width` x `height` window instead of a fullscreen one.
+    pub fn windowed(mut self, width: u32, height: u32) -> Self {
+        self.windowed = Some((width, height));
+        self
+    }
+
+    /// Stencil test of objects, against the reference set with
+    /// `XApp::set_stencil_reference` and the values written by objects with
+    /// `RenderObject::stencil_mask`. Default keep the stencil untouched.
+    pub fn stencil(mut self, stencil: StencilState) -> Self {
+        self.stencil = stencil;
+        self
+    }
+
+    /// Save window size, position and fullscreen state on quit, and reopen
+    /// the window the same way on next run. Saved state replace `display`,
+    /// `position` and `windowed`; they are used when nothing is saved yet
+    /// or the file is corrupt.
+    pub fn remember_window_state(mut self, enable: bool) -> Self {
+        self.remember_window_state = enable;
+        self
+    }
+
+    /// Faces of object triangles that are not drawn, `Back` by default.
+    /// Triangles are front facing when their vertices go the `front_face`
+    /// way on screen, counter clockwise by default, so with the default a
+    /// clockwise triangle, or any triangle seen from behind, is invisible.
+    /// `None` draw both sides, for 2D or double sided geometry.
+    pub fn cull_mode(mut self, cull_mode: Option<Face>) -> Self {
+        self.cull_mode = cull_mode;
+        self
+    }
+
+    /// Winding of front facing triangles, `Ccw` by default. Use `Cw` for
+    /// meshes authored clockwise, which otherwise look inside out with
+    /// back face culling. Combine with `cull_mode`: `Cw` with `Back` cull
+    /// the counter clockwise triangles instead.
+    pub fn front_face(mut self, front_face: FrontFace) -> Self {
+        self.front_face = front_face;
+        self
+    }
+
+    /// Draw opaque objects twice: a first pass write only their depth, the
+    /// color pass then shade only the nearest fragment of each pixel with
+    /// depth compare `Equal`. Faster on scenes with a lot of overdraw and
+    /// costly fragments. The prepass fill the depth buffer so the depth
+    /// test of the main pass resolve visibility, opaque objects are still
+    /// drawn in submission order. Stencil masks, transparent objects,
+    /// other topologies, sprites and particles are drawn as usual, and
+    /// the prepass is skipped in wireframe mode. With `TIMESTAMP_QUERY`
+    /// both passes are timed in the debug overlay.
+    pub fn depth_prepass(mut self, enable: bool) -> Self {
+        self.depth_prepass = enable;
+        self
+    }
+
+    /// Skip objects whose bounds are entirely out of the camera view,
+    /// enabled by default. Objects are only culled while a single instance
+    /// is drawn, see `XApp::culled_count`.
+    pub fn frustum_culling(mut self, enable: bool) -> Self {
+        self.frustum_culling = enable;
+        self
+    }
+
+    /// How the window is composited with the desktop, `Auto` by default.
+    /// `PreMultiplied` and `PostMultiplied` make a borderless window whose
+    /// transparent pixels, e.g. cleared with `Color::TRANSPARENT`, show
+    /// what is behind it, for overlays. A mode the surface doesn't support
+    /// fall back to `Auto` with a warning, see
+    /// `XApp::surface_capabilities`. Whether the desktop really show
+    /// through also depend on the window system compositor.
+    pub fn alpha_mode(mut self, alpha_mode: CompositeAlphaMode) -> Self {
+        self.alpha_mode = alpha_mode;
+        self
+    }
+
+    /// Upload camera, object uniforms, light and instances of each frame
+    /// through a `StagingBelt` copied by the frame commands, instead of
+    /// one `Queue::write_buffer` per buffer. Disabled by default until it
+    /// is measured faster, enable it to compare CPU and GPU frame time in
+    /// the debug overlay.
+    pub fn staging_belt(mut self, enable: bool) -> Self {
+        self.staging_belt = enable;
+        self
+    }
+
+    /// Draw a frame only when something changed, any event or
+    /// `XApp::request_redraw`, and sleep waiting for events otherwise.
+    /// For tool-like apps idle most of the time, games keep the default
+    /// continuous rendering. `Game::update` and `Game::render` only run
+    /// for drawn frames, call `request_redraw` from them to keep
+    /// animating.
+    pub fn on_demand(mut self, enable: bool) -> Self {
+        self.on_demand = enable;
+        self
+    }
+
+    /// Install `env_logger` on debug build, enabled by default. It is
+    /// skipped when a logger is already set, disable it to set one after
+    /// building the app or to keep the log silent.
+    pub fn init_logger(mut self, enable: bool) -> Self {
+        self.init_logger = enable;
+        self
+    }
+
+    /// On debug build, warn when nearly every triangle of an open loaded
+    /// mesh is culled, see `XApp::check_winding`. Enabled by default.
+    pub fn check_winding(mut self, enable: bool) -> Self {
+        self.check_winding = enable;
+        self
+    }
+
+    /// Most verbose level shown by the installed `env_logger`, instead of
+    /// `RUST_LOG`. `Info` shows init choices and state changes, `Trace`
+    /// adds the full adapter and surface capabilities. The one line init
+    /// summary is always written, see `XApp::init_summary`.
+    pub fn log_level(mut self, level: LevelFilter) -> Self {
+        self.log_level = Some(level);
+        self
+    }
+
+    /// Highest anisotropy of texture samplers, clamped to `1..=16`, 16 by
+    /// default. Lowered to 1 when the adapter can't filter anisotropically,
+    /// see `SamplerOptions::anisotropy`.
+    pub fn max_anisotropy(mut self, max: u16) -> Self {
+        self.max_anisotropy = max.clamp(1, texture::MAX_ANISOTROPY);
+        self
+    }
+
+    /// Create app with an SDL window, fullscreen unless `windowed` is set.
+    pub fn build<'l>(self) -> Result<XApp<'l>, String> {
+        let saved = self
+            .remember_window_state
+            .then(|| WindowState::load(&self.title))
+            .flatten();
+        let builder = match saved {
+            Some(state) => state.apply(self),
+            None => self,
+        };
+        XApp::with_window(&builder)
+    }
+
+    /// Create app without window, see `XApp::new_headless`.
+    pub fn build_headless<'l>(self, width: u32, height: u32) -> Result<XApp<'l>, String> {
+        XApp::headless(&self, width, height)
+    }
+}
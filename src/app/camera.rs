@@ -0,0 +1,148 @@
+/// How `Camera2D` project the scene.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Projection {
+    /// Parallel projection, `z` only matter for depth.
+    Orthographic,
+    /// Camera looking toward `+z` from in front of the `z = 0` plane, far
+    /// away enough to show the same area of that plane as `Orthographic`.
+    /// Geometry at higher `z` look smaller.
+    Perspective {
+        /// Vertical field of view in radian.
+        fov_y: f32,
+        /// Distance of the near and far clip planes from the camera.
+        near: f32,
+        far: f32,
+    },
+}
+
+impl Projection {
+    /// 60 degree perspective with clip planes at 0.1 and 100.0.
+    pub fn perspective() -> Self {
+        Projection::Perspective {
+            fov_y: std::f32::consts::FRAC_PI_3,
+            near: 0.1,
+            far: 100.0,
+        }
+    }
+}
+
+/// Camera for 2D scene, orthographic by default.
+///
+/// At zoom `1.0` the viewport shows 2 world units vertically (`-1.0..1.0`)
+/// and `2.0 * aspect` units horizontally, so geometry keeps its proportion
+/// whatever the window shape is. With a perspective projection this hold
+/// for the `z = 0` plane.
+#[derive(Copy, Clone, Debug)]
+pub struct Camera2D {
+    pub position: [f32; 2],
+    pub zoom: f32,
+    viewport: [f32; 2],
+    projection: Projection,
+}
+
+impl Camera2D {
+    const MIN_ZOOM: f32 = 0.01;
+    const MAX_ZOOM: f32 = 100.0;
+
+    pub fn new(width: u32, height: u32) -> Self {
+        Camera2D {
+            position: [0.0, 0.0],
+            zoom: 1.0,
+            viewport: [width.max(1) as f32, height.max(1) as f32],
+            projection: Projection::Orthographic,
+        }
+    }
+
+    pub fn projection(&self) -> Projection {
+        self.projection
+    }
+
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.projection = projection;
+    }
+
+    pub fn viewport(&self) -> [f32; 2] {
+        self.viewport
+    }
+
+    pub fn set_viewport(&mut self, width: u32, height: u32) {
+        self.viewport = [width.max(1) as f32, height.max(1) as f32];
+    }
+
+    pub fn aspect(&self) -> f32 {
+        self.viewport[0] / self.viewport[1]
+    }
+
+    /// Size of one pixel in world units.
+    pub fn world_per_pixel(&self) -> f32 {
+        2.0 / (self.viewport[1] * self.zoom)
+    }
+
+    /// Multiply zoom by `factor`, kept in a sane range.
+    pub fn zoom_by(&mut self, factor: f32) {
+        self.zoom = (self.zoom * factor).clamp(Self::MIN_ZOOM, Self::MAX_ZOOM);
+    }
+
+    /// Move camera following a drag of `dx`, `dy` pixels on screen.
+    /// Screen y grows downward while world y grows upward.
+    pub fn pan_by_pixels(&mut self, dx: f32, dy: f32) {
+        let scale = self.world_per_pixel();
+        self.position[0] -= dx * scale;
+        self.position[1] += dy * scale;
+    }
+
+    /// Convert window pixel coordinate to world coordinate.
+    pub fn screen_to_world(&self, x: f32, y: f32) -> [f32; 2] {
+        let scale = self.world_per_pixel();
+        [
+            self.position[0] + (x - self.viewport[0] * 0.5) * scale,
+            self.position[1] - (y - self.viewport[1] * 0.5) * scale,
+        ]
+    }
+
+    /// View projection matrix of the current projection, column major.
+    pub fn view_proj(&self) -> [[f32; 4]; 4] {
+        match self.projection {
+            Projection::Orthographic => self.orthographic(),
+            Projection::Perspective { fov_y, near, far } => self.perspective(fov_y, near, far),
+        }
+    }
+
+    /// Depth `-1.0..1.0` is mapped to wgpu clip range `0.0..1.0`.
+    fn orthographic(&self) -> [[f32; 4]; 4] {
+        let half_h = 1.0 / self.zoom;
+        let half_w = self.aspect() / self.zoom;
+        [
+            [1.0 / half_w, 0.0, 0.0, 0.0],
+            [0.0, 1.0 / half_h, 0.0, 0.0],
+            [0.0, 0.0, 0.5, 0.0],
+            [
+                -self.position[0] / half_w,
+                -self.position[1] / half_h,
+                0.5,
+                1.0,
+            ],
+        ]
+    }
+
+    /// Left handed perspective, depth `near..far` in front of the camera
+    /// is mapped to wgpu clip range `0.0..1.0`.
+    fn perspective(&self, fov_y: f32, near: f32, far: f32) -> [[f32; 4]; 4] {
+        let f = 1.0 / (fov_y * 0.5).tan();
+        // half height visible on z = 0 is 1 / zoom, as orthographic
+        let distance = f / self.zoom;
+        let x = f / self.aspect();
+        let depth = far / (far - near);
+        [
+            [x, 0.0, 0.0, 0.0],
+            [0.0, f, 0.0, 0.0],
+            [0.0, 0.0, depth, 1.0],
+            [
+                -self.position[0] * x,
+                -self.position[1] * f,
+                (distance - near) * depth,
+                distance,
+            ],
+        ]
+    }
+}
@@ -0,0 +1,78 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Instant;
+
+/// Time source of the frame `dt` given to `Game`, see `XApp::set_clock`.
+pub trait Clock {
+    /// Seconds since an arbitrary start, never decreasing.
+    fn now(&self) -> f64;
+}
+
+/// Shared clock, so the caller can keep a handle on a clock given to `XApp`.
+impl<C: Clock + ?Sized> Clock for Rc<C> {
+    fn now(&self) -> f64 {
+        (**self).now()
+    }
+}
+
+/// Wall clock time, default clock of `XApp`.
+#[derive(Debug)]
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        SystemClock {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> f64 {
+        self.start.elapsed().as_secs_f64()
+    }
+}
+
+/// Clock moving `step` seconds forward on every `now`, so every frame of
+/// `XApp::run` get the same `dt` whatever the real frame rate is. Used to
+/// replay or test animation deterministically.
+#[derive(Debug)]
+pub struct MockClock {
+    time: Cell<f64>,
+    step: f64,
+}
+
+impl MockClock {
+    pub fn new(step: f64) -> Self {
+        MockClock {
+            time: Cell::new(0.0),
+            step,
+        }
+    }
+
+    /// Current time without advancing it.
+    pub fn time(&self) -> f64 {
+        self.time.get()
+    }
+
+    /// Move time forward by `seconds` in addition to the steps.
+    pub fn advance(&self, seconds: f64) {
+        self.time.set(self.time.get() + seconds);
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> f64 {
+        let now = self.time.get();
+        self.time.set(now + self.step);
+        now
+    }
+}
@@ -0,0 +1,176 @@
+use std::time::Instant;
+
+use wgpu::{
+    util::DeviceExt, BindGroup, BindGroupLayout, Buffer, CommandEncoder, ComputePipeline, Device,
+    Queue,
+};
+
+use super::{RenderObject, Vertex};
+
+const WORKGROUP_SIZE: u32 = 64;
+
+/// Uniform of compute shader, matches `Params` in `compute.wgsl`.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct ComputeParams {
+    time: f32,
+    amplitude: f32,
+    frequency: f32,
+    speed: f32,
+}
+
+/// Compute storage of one object.
+pub(crate) struct ComputeBinding {
+    // keep untouched copy of vertices, the wave is computed from it
+    _base_buffer: Buffer,
+    bind_group: BindGroup,
+    vertex_count: u32,
+}
+
+/// Compute pipeline moving vertices of every object along a sine wave.
+pub(crate) struct ComputeAnimator {
+    pipeline: ComputePipeline,
+    object_layout: BindGroupLayout,
+    params_buffer: Buffer,
+    params_bind_group: BindGroup,
+    start: Instant,
+}
+
+impl ComputeAnimator {
+    pub(crate) fn new(device: &Device) -> Self {
+        let shader = device.create_shader_module(wgpu::include_wgsl!("compute.wgsl"));
+
+        let storage_entry = |binding, read_only| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let object_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("compute_object_bind_group_layout"),
+            entries: &[storage_entry(0, true), storage_entry(1, false)],
+        });
+        let params_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("compute_params_bind_group_layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("compute params uniform"),
+            contents: bytemuck::bytes_of(&Self::params(0.0)),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let params_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("compute params bind group"),
+            layout: &params_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: params_buffer.as_entire_binding(),
+            }],
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("compute_pipeline_layout"),
+            bind_group_layouts: &[&object_layout, &params_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("compute_pipeline"),
+            layout: Some(&layout),
+            module: &shader,
+            entry_point: Some("cs_main"),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            cache: None,
+        });
+
+        ComputeAnimator {
+            pipeline,
+            object_layout,
+            params_buffer,
+            params_bind_group,
+            start: Instant::now(),
+        }
+    }
+
+    fn params(time: f32) -> ComputeParams {
+        ComputeParams {
+            time,
+            amplitude: 0.1,
+            frequency: 4.0,
+            speed: 2.0,
+        }
+    }
+
+    /// Bind `vertex_buffer` (created with `STORAGE` usage) as compute output.
+    pub(crate) fn bind_object(
+        &self,
+        device: &Device,
+        vertex_buffer: &Buffer,
+        vertices: &[Vertex],
+    ) -> ComputeBinding {
+        let base_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("compute base vertices"),
+            contents: bytemuck::cast_slice(vertices),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("compute object bind group"),
+            layout: &self.object_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: base_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: vertex_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        ComputeBinding {
+            _base_buffer: base_buffer,
+            bind_group,
+            vertex_count: vertices.len() as u32,
+        }
+    }
+
+    /// Update time and record one compute pass over every bound object.
+    pub(crate) fn dispatch(
+        &self,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        objects: &[RenderObject],
+    ) {
+        let time = self.start.elapsed().as_secs_f32();
+        queue.write_buffer(
+            &self.params_buffer,
+            0,
+            bytemuck::bytes_of(&Self::params(time)),
+        );
+
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Compute Pass"),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(1, &self.params_bind_group, &[]);
+        for binding in objects.iter().filter_map(|o| o.compute.as_ref()) {
+            pass.set_bind_group(0, &binding.bind_group, &[]);
+            pass.dispatch_workgroups(binding.vertex_count.div_ceil(WORKGROUP_SIZE), 1, 1);
+        }
+    }
+}
@@ -0,0 +1,35 @@
+// Compute shader animating vertex positions with a sine wave.
+
+// Vertex layout matches `Vertex`: position, color then normal (3 f32 each).
+const VERTEX_STRIDE: u32 = 9u;
+
+struct Params {
+    time: f32,
+    amplitude: f32,
+    frequency: f32,
+    speed: f32,
+};
+
+// original vertices, never modified
+@group(0) @binding(0)
+var<storage, read> base_vertices: array<f32>;
+
+// vertex buffer used by render pass
+@group(0) @binding(1)
+var<storage, read_write> vertices: array<f32>;
+
+@group(1) @binding(0)
+var<uniform> params: Params;
+
+@compute @workgroup_size(64)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let count = arrayLength(&base_vertices) / VERTEX_STRIDE;
+    if (id.x >= count) {
+        return;
+    }
+
+    let i = id.x * VERTEX_STRIDE;
+    let x = base_vertices[i];
+    let y = base_vertices[i + 1u];
+    vertices[i + 1u] = y + params.amplitude * sin(params.time * params.speed + x * params.frequency);
+}
@@ -0,0 +1,91 @@
+#[cfg(debug_assertions)]
+use log::info;
+use log::warn;
+use sdl2::{controller::GameController, event::Event, GameControllerSubsystem, Sdl};
+
+/// Game controllers plugged in, only player 1 is opened.
+///
+/// Player 1 is the first controller connected. When it is removed the next
+/// connected controller, if any, become player 1.
+pub(crate) struct Controllers {
+    subsystem: Option<GameControllerSubsystem>,
+    player1: Option<GameController>,
+}
+
+impl Controllers {
+    /// Without `sdl`, e.g. headless app, there is never any controller.
+    pub(crate) fn new(sdl: Option<&Sdl>) -> Self {
+        let subsystem = sdl.and_then(|sdl| match sdl.game_controller() {
+            Ok(x) => Some(x),
+            Err(e) => {
+                warn!("Game controller not available: {}", e);
+                None
+            }
+        });
+        Controllers {
+            subsystem,
+            player1: None,
+        }
+    }
+
+    /// Open or forget controllers as they are connected and removed.
+    /// Controllers already connected at start are reported as added too.
+    pub(crate) fn handle_event(&mut self, event: &Event) {
+        match *event {
+            Event::ControllerDeviceAdded { which, .. } if self.player1.is_none() => {
+                self.open(which);
+            }
+            Event::ControllerDeviceRemoved { which, .. }
+                if self.player1.as_ref().map(|x| x.instance_id()) == Some(which) =>
+            {
+                self.player1 = None;
+                self.open_next();
+            }
+            _ => {}
+        }
+    }
+
+    /// Open joystick `index` as player 1.
+    fn open(&mut self, index: u32) {
+        let Some(subsystem) = &self.subsystem else {
+            return;
+        };
+        match subsystem.open(index) {
+            Ok(controller) => {
+                #[cfg(debug_assertions)]
+                info!("Player 1 controller: {}", controller.name());
+                self.player1 = Some(controller);
+            }
+            Err(e) => warn!("Cannot open controller {}: {}", index, e),
+        }
+    }
+
+    /// Open the first connected controller still there.
+    fn open_next(&mut self) {
+        let Some(subsystem) = &self.subsystem else {
+            return;
+        };
+        let count = subsystem.num_joysticks().unwrap_or(0);
+        if let Some(index) = (0..count).find(|&i| subsystem.is_game_controller(i)) {
+            self.open(index);
+        }
+    }
+
+    pub(crate) fn player1(&self) -> Option<&GameController> {
+        self.player1.as_ref()
+    }
+
+    /// Rumble player 1 controller, does nothing without controller or when
+    /// it has no rumble motor.
+    pub(crate) fn rumble(&mut self, low_freq: u16, high_freq: u16, duration_ms: u32) {
+        let Some(controller) = &mut self.player1 else {
+            return;
+        };
+        if !controller.has_rumble() {
+            return;
+        }
+        if let Err(e) = controller.set_rumble(low_freq, high_freq, duration_ms) {
+            warn!("Controller rumble failed: {}", e);
+        }
+    }
+}
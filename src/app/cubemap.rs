@@ -0,0 +1,188 @@
+use wgpu::{Device, TextureFormat, TextureView};
+
+/// Near and far plane of the capture projection.
+const NEAR: f32 = 0.1;
+const FAR: f32 = 100.0;
+
+/// Forward and up direction of each face, in layer order +X, -X, +Y, -Y,
+/// +Z, -Z. Up follow the cube sampling convention of wgpu, row 0 of a face
+/// is its top.
+const FACES: [([f32; 3], [f32; 3]); 6] = [
+    ([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ([-1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ([0.0, 1.0, 0.0], [0.0, 0.0, -1.0]),
+    ([0.0, -1.0, 0.0], [0.0, 0.0, 1.0]),
+    ([0.0, 0.0, 1.0], [0.0, 1.0, 0.0]),
+    ([0.0, 0.0, -1.0], [0.0, 1.0, 0.0]),
+];
+
+/// Cube texture the scene is rendered into with `XApp::render_cubemap`,
+/// then sampled through `view` for reflections or skyboxes.
+pub struct CubeTarget {
+    texture: wgpu::Texture,
+    view: TextureView,
+    face_views: Vec<TextureView>,
+    depth_stencil_view: TextureView,
+}
+
+impl CubeTarget {
+    pub(crate) fn new(
+        device: &Device,
+        size: u32,
+        format: TextureFormat,
+        depth_stencil_view: TextureView,
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("cubemap target"),
+            size: wgpu::Extent3d {
+                width: size,
+                height: size,
+                depth_or_array_layers: 6,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("cubemap view"),
+            dimension: Some(wgpu::TextureViewDimension::Cube),
+            ..Default::default()
+        });
+        // one 2D view per layer, each is the color attachment of a pass
+        let face_views = (0..6)
+            .map(|layer| {
+                texture.create_view(&wgpu::TextureViewDescriptor {
+                    label: Some("cubemap face view"),
+                    dimension: Some(wgpu::TextureViewDimension::D2),
+                    base_array_layer: layer,
+                    array_layer_count: Some(1),
+                    ..Default::default()
+                })
+            })
+            .collect();
+        CubeTarget {
+            texture,
+            view,
+            face_views,
+            depth_stencil_view,
+        }
+    }
+
+    pub fn texture(&self) -> &wgpu::Texture {
+        &self.texture
+    }
+
+    /// Cube view of the six faces, for `texture_cube` bindings.
+    pub fn view(&self) -> &TextureView {
+        &self.view
+    }
+
+    /// Width and height of each face.
+    pub fn size(&self) -> u32 {
+        self.texture.width()
+    }
+
+    pub(crate) fn face_views(&self) -> &[TextureView] {
+        &self.face_views
+    }
+
+    pub(crate) fn depth_stencil_view(&self) -> &TextureView {
+        &self.depth_stencil_view
+    }
+}
+
+/// View projection of cube `face` seen from `eye`, 90 degree field of view
+/// so the six faces cover every direction. Column major.
+pub(crate) fn face_view_proj(eye: [f32; 3], face: usize) -> [[f32; 4]; 4] {
+    let (forward, up) = FACES[face];
+    let depth = FAR / (FAR - NEAR);
+    let proj = [
+        [1.0, 0.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0, 0.0],
+        [0.0, 0.0, depth, 1.0],
+        [0.0, 0.0, -NEAR * depth, 0.0],
+    ];
+    mul(proj, look_at(eye, forward, up))
+}
+
+/// View matrix of a camera at `eye` looking toward unit `forward`, `up`
+/// must not be parallel to it. Column major.
+pub(crate) fn look_at(eye: [f32; 3], forward: [f32; 3], up: [f32; 3]) -> [[f32; 4]; 4] {
+    // left handed basis, right = up x forward
+    let z = forward;
+    let x = normalize(cross(up, z));
+    let y = cross(z, x);
+    [
+        [x[0], y[0], z[0], 0.0],
+        [x[1], y[1], z[1], 0.0],
+        [x[2], y[2], z[2], 0.0],
+        [-dot(x, eye), -dot(y, eye), -dot(z, eye), 1.0],
+    ]
+}
+
+fn normalize(a: [f32; 3]) -> [f32; 3] {
+    let length = dot(a, a).sqrt();
+    [a[0] / length, a[1] / length, a[2] / length]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// `a * b` of column major matrices.
+pub(crate) fn mul(a: [[f32; 4]; 4], b: [[f32; 4]; 4]) -> [[f32; 4]; 4] {
+    let mut out = [[0.0; 4]; 4];
+    for (col, out_col) in out.iter_mut().enumerate() {
+        for (row, value) in out_col.iter_mut().enumerate() {
+            *value = (0..4).map(|k| a[k][row] * b[col][k]).sum();
+        }
+    }
+    out
+}
+
+/// Inverse of column major `m`, `None` when it isn't invertible.
+pub(crate) fn invert(m: [[f32; 4]; 4]) -> Option<[[f32; 4]; 4]> {
+    // Gauss-Jordan on columns, which invert the transpose, and the
+    // transpose of the inverse is the inverse of the transpose
+    let mut a = m;
+    let mut out = [
+        [1.0, 0.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ];
+    for i in 0..4 {
+        let pivot = (i..4).max_by(|&x, &y| a[x][i].abs().total_cmp(&a[y][i].abs()))?;
+        if a[pivot][i].abs() < f32::EPSILON {
+            return None;
+        }
+        a.swap(i, pivot);
+        out.swap(i, pivot);
+        let scale = 1.0 / a[i][i];
+        for k in 0..4 {
+            a[i][k] *= scale;
+            out[i][k] *= scale;
+        }
+        for j in 0..4 {
+            if j != i {
+                let factor = a[j][i];
+                for k in 0..4 {
+                    a[j][k] -= factor * a[i][k];
+                    out[j][k] -= factor * out[i][k];
+                }
+            }
+        }
+    }
+    Some(out)
+}
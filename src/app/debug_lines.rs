@@ -0,0 +1,171 @@
+use std::cell::{Cell, RefCell};
+
+use wgpu::{BindGroup, BindGroupLayout, Buffer, Device, RenderPass, RenderPipeline, TextureFormat};
+
+use super::validate::assert_draw_range;
+
+/// Line vertex, matches `LineInput` in `debug_lines.wgsl`.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct LineVertex {
+    position: [f32; 3],
+    color: [f32; 4],
+}
+
+impl LineVertex {
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<LineVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
+
+/// Lines queued during a frame, drawn once over the scene then cleared.
+pub(crate) struct DebugLines {
+    pipeline: RenderPipeline,
+    vertex_buffer: Option<Buffer>,
+    queued: RefCell<Vec<LineVertex>>,
+    /// Vertices uploaded for the frame being drawn.
+    drawn: Cell<u32>,
+}
+
+impl DebugLines {
+    pub(crate) fn new(
+        device: &Device,
+        camera_layout: &BindGroupLayout,
+        format: TextureFormat,
+        depth_stencil: wgpu::DepthStencilState,
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::include_wgsl!("debug_lines.wgsl"));
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("debug_lines_pipeline_layout"),
+            bind_group_layouts: &[camera_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("debug_lines_pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                buffers: &[LineVertex::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::LineList,
+                ..Default::default()
+            },
+            depth_stencil: Some(depth_stencil),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        DebugLines {
+            pipeline,
+            vertex_buffer: None,
+            queued: RefCell::new(Vec::new()),
+            drawn: Cell::new(0),
+        }
+    }
+
+    /// Queue line from `a` to `b`, the vertex buffer grow to fit it.
+    pub(crate) fn draw_line(&mut self, device: &Device, a: [f32; 3], b: [f32; 3], color: [f32; 4]) {
+        let queued = self.queued.get_mut();
+        queued.extend_from_slice(&[
+            LineVertex { position: a, color },
+            LineVertex { position: b, color },
+        ]);
+        let size = std::mem::size_of_val(queued.as_slice()) as wgpu::BufferAddress;
+        if self.vertex_buffer.as_ref().map_or(0, |x| x.size()) < size {
+            self.vertex_buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("debug line vertex buffer"),
+                size: size.next_power_of_two(),
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+        }
+    }
+
+    /// Queue the 12 edges of the axis aligned box from `min` to `max`.
+    pub(crate) fn draw_box(
+        &mut self,
+        device: &Device,
+        min: [f32; 3],
+        max: [f32; 3],
+        color: [f32; 4],
+    ) {
+        // corner `i` take x, y and z from `max` when bit 0, 1 and 2 are set
+        let corner = |i: usize| {
+            let mut p = min;
+            for (axis, x) in p.iter_mut().enumerate() {
+                if i & (1 << axis) != 0 {
+                    *x = max[axis];
+                }
+            }
+            p
+        };
+        for i in 0..8 {
+            for axis in 0..3 {
+                // each edge once, from the corner with the lower bit
+                if i & (1 << axis) == 0 {
+                    self.draw_line(device, corner(i), corner(i | 1 << axis), color);
+                }
+            }
+        }
+    }
+
+    /// Take queued lines for the frame being recorded, `None` when there
+    /// is nothing to draw. Lines are cleared for the next frame.
+    pub(crate) fn take(&self) -> Option<(&Buffer, Vec<u8>)> {
+        let vertices = std::mem::take(&mut *self.queued.borrow_mut());
+        self.drawn.set(vertices.len() as u32);
+        match &self.vertex_buffer {
+            Some(buffer) if !vertices.is_empty() => {
+                Some((buffer, bytemuck::cast_slice(&vertices).to_vec()))
+            }
+            _ => None,
+        }
+    }
+
+    pub(crate) fn draw(&self, render_pass: &mut RenderPass, camera_bind_group: &BindGroup) {
+        let buffer = match &self.vertex_buffer {
+            Some(x) if self.drawn.get() > 0 => x,
+            _ => return,
+        };
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, camera_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, buffer.slice(..));
+        let range = 0..self.drawn.get();
+        assert_draw_range(
+            "Debug line vertex",
+            &range,
+            buffer,
+            std::mem::size_of::<LineVertex>(),
+        );
+        render_pass.draw(range, 0..1);
+    }
+}
@@ -0,0 +1,27 @@
+// Debug lines in world space, two vertices per line.
+
+@group(0) @binding(0)
+var<uniform> camera: mat4x4<f32>;
+
+struct LineInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+};
+
+@vertex
+fn vs_main(line: LineInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = camera * vec4<f32>(line.position, 1.0);
+    out.color = line.color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
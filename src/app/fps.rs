@@ -0,0 +1,44 @@
+use std::time::{Duration, Instant};
+
+/// Count rendered frames and compute frame rate once per second.
+#[derive(Debug)]
+pub struct FpsCounter {
+    frames: u32,
+    since: Instant,
+    fps: f32,
+}
+
+impl FpsCounter {
+    const UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+
+    pub fn new() -> Self {
+        FpsCounter {
+            frames: 0,
+            since: Instant::now(),
+            fps: 0.0,
+        }
+    }
+
+    /// Register one frame. Return `true` when `fps` got a new value.
+    pub fn tick(&mut self) -> bool {
+        self.frames += 1;
+        let elapsed = self.since.elapsed();
+        if elapsed < Self::UPDATE_INTERVAL {
+            return false;
+        }
+        self.fps = self.frames as f32 / elapsed.as_secs_f32();
+        self.frames = 0;
+        self.since = Instant::now();
+        true
+    }
+
+    pub fn fps(&self) -> f32 {
+        self.fps
+    }
+}
+
+impl Default for FpsCounter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
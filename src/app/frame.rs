@@ -0,0 +1,105 @@
+use std::cell::Cell;
+
+use wgpu::{util::DeviceExt, BindGroup, BindGroupLayout, Buffer, Device, Queue};
+
+use super::object::ObjectUniforms;
+
+/// Buffers rewritten every frame with their bind groups.
+pub(crate) struct FrameResources {
+    pub(crate) camera_buffer: Buffer,
+    /// Camera and the shared render mode uniform.
+    pub(crate) camera_bind_group: BindGroup,
+    pub(crate) object_uniforms: ObjectUniforms,
+}
+
+/// One `FrameResources` per frame in flight, used in turn so a frame
+/// never write a buffer the GPU may still read for a previous frame.
+pub(crate) struct FrameRing {
+    frames: Vec<FrameResources>,
+    index: Cell<usize>,
+}
+
+impl FrameRing {
+    /// `count` sets, at least one, with `camera` as initial camera data.
+    pub(crate) fn new(
+        device: &Device,
+        count: usize,
+        camera_layout: &BindGroupLayout,
+        model_layout: &BindGroupLayout,
+        render_mode_buffer: &Buffer,
+        camera: &[u8],
+    ) -> Self {
+        let frames = (0..count.max(1))
+            .map(|_| {
+                let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("camera uniform"),
+                    contents: camera,
+                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                });
+                let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("camera bind group"),
+                    layout: camera_layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: camera_buffer.as_entire_binding(),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: render_mode_buffer.as_entire_binding(),
+                        },
+                    ],
+                });
+                FrameResources {
+                    camera_buffer,
+                    camera_bind_group,
+                    object_uniforms: ObjectUniforms::new(device, model_layout, 16),
+                }
+            })
+            .collect();
+
+        FrameRing {
+            frames,
+            index: Cell::new(0),
+        }
+    }
+
+    /// Resources of the frame being recorded.
+    pub(crate) fn current(&self) -> &FrameResources {
+        &self.frames[self.index.get()]
+    }
+
+    pub(crate) fn index(&self) -> usize {
+        self.index.get()
+    }
+
+    #[cfg(debug_assertions)]
+    pub(crate) fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Write `camera` to every set, the frames recorded next see it
+    /// whichever set they use.
+    pub(crate) fn write_camera(&self, queue: &Queue, camera: &[u8]) {
+        for frame in &self.frames {
+            queue.write_buffer(&frame.camera_buffer, 0, camera);
+        }
+    }
+
+    /// Move to the next set, call before recording a frame.
+    pub(crate) fn advance(&self) {
+        self.index.set((self.index.get() + 1) % self.frames.len());
+    }
+
+    /// Make room for `count` objects in every set.
+    pub(crate) fn reserve(
+        &mut self,
+        device: &Device,
+        model_layout: &BindGroupLayout,
+        count: usize,
+    ) {
+        for frame in &mut self.frames {
+            frame.object_uniforms.reserve(device, model_layout, count);
+        }
+    }
+}
@@ -0,0 +1,40 @@
+/// Six planes bounding what a view projection matrix shows, used to skip
+/// objects out of view. Each plane is `[a, b, c, d]` with the inside where
+/// `a * x + b * y + c * z + d >= 0`.
+pub(crate) struct Frustum {
+    planes: [[f32; 4]; 6],
+}
+
+impl Frustum {
+    /// Planes of column major `view_proj`, with wgpu clip depth
+    /// `0.0..1.0`.
+    pub(crate) fn from_view_proj(view_proj: &[[f32; 4]; 4]) -> Self {
+        let row = |i: usize| -> [f32; 4] { std::array::from_fn(|col| view_proj[col][i]) };
+        let (x, y, z, w) = (row(0), row(1), row(2), row(3));
+        let add = |a: [f32; 4], b: [f32; 4]| -> [f32; 4] { std::array::from_fn(|i| a[i] + b[i]) };
+        let sub = |a: [f32; 4], b: [f32; 4]| -> [f32; 4] { std::array::from_fn(|i| a[i] - b[i]) };
+        Frustum {
+            planes: [
+                add(w, x),
+                sub(w, x),
+                add(w, y),
+                sub(w, y),
+                // near is at depth 0.0, not -w like OpenGL
+                z,
+                sub(w, z),
+            ],
+        }
+    }
+
+    /// False only when the box of `min` and `max` corners is entirely
+    /// outside one of the planes. Boxes crossing a plane are kept, as are
+    /// a few boxes near the frustum corners that are actually out of view.
+    pub(crate) fn intersects(&self, min: [f32; 3], max: [f32; 3]) -> bool {
+        self.planes.iter().all(|plane| {
+            // corner of the box furthest along the plane normal
+            let corner: [f32; 3] =
+                std::array::from_fn(|i| if plane[i] >= 0.0 { max[i] } else { min[i] });
+            plane[0] * corner[0] + plane[1] * corner[1] + plane[2] * corner[2] + plane[3] >= 0.0
+        })
+    }
+}
@@ -0,0 +1,63 @@
+// FXAA, appended to post.wgsl when enabled with `XAppBuilder::fxaa`.
+// Simplified FXAA 3.11 "console" variant: blur along the edge direction
+// found from the luma of the 4 diagonal neighbours.
+
+const FXAA_SPAN_MAX: f32 = 8.0;
+const FXAA_REDUCE_MUL: f32 = 0.125;
+const FXAA_REDUCE_MIN: f32 = 0.0078125;
+
+// sampled colors are linear, sqrt bring luma close to perceived brightness
+fn fxaa_luma(color: vec3<f32>) -> f32 {
+    return sqrt(dot(color, vec3<f32>(0.299, 0.587, 0.114)));
+}
+
+fn fxaa_sample(uv: vec2<f32>) -> vec3<f32> {
+    return textureSample(scene_texture, scene_sampler, uv).rgb;
+}
+
+@fragment
+fn fs_fxaa(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(scene_texture));
+    let center = textureSample(scene_texture, scene_sampler, in.uv);
+
+    let luma_nw = fxaa_luma(fxaa_sample(in.uv + vec2<f32>(-1.0, -1.0) * texel));
+    let luma_ne = fxaa_luma(fxaa_sample(in.uv + vec2<f32>(1.0, -1.0) * texel));
+    let luma_sw = fxaa_luma(fxaa_sample(in.uv + vec2<f32>(-1.0, 1.0) * texel));
+    let luma_se = fxaa_luma(fxaa_sample(in.uv + vec2<f32>(1.0, 1.0) * texel));
+    let luma_m = fxaa_luma(center.rgb);
+
+    let luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    let luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    // gradient across the edge, rotated to point along it
+    var dir = vec2<f32>(
+        -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+        (luma_nw + luma_sw) - (luma_ne + luma_se),
+    );
+    let dir_reduce = max(
+        (luma_nw + luma_ne + luma_sw + luma_se) * (0.25 * FXAA_REDUCE_MUL),
+        FXAA_REDUCE_MIN,
+    );
+    let rcp_dir_min = 1.0 / (min(abs(dir.x), abs(dir.y)) + dir_reduce);
+    dir = clamp(
+        dir * rcp_dir_min,
+        vec2<f32>(-FXAA_SPAN_MAX),
+        vec2<f32>(FXAA_SPAN_MAX),
+    ) * texel;
+
+    let rgb_a = 0.5 * (
+        fxaa_sample(in.uv + dir * (1.0 / 3.0 - 0.5)) +
+        fxaa_sample(in.uv + dir * (2.0 / 3.0 - 0.5))
+    );
+    let rgb_b = rgb_a * 0.5 + 0.25 * (
+        fxaa_sample(in.uv + dir * -0.5) +
+        fxaa_sample(in.uv + dir * 0.5)
+    );
+
+    // wider blur went past the edge, keep the narrow one
+    let luma_b = fxaa_luma(rgb_b);
+    if luma_b < luma_min || luma_b > luma_max {
+        return vec4<f32>(rgb_a, center.a);
+    }
+    return vec4<f32>(rgb_b, center.a);
+}
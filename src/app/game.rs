@@ -0,0 +1,49 @@
+use sdl2::keyboard::Keycode;
+
+use super::XApp;
+
+/// User code driven by `XApp::run`.
+///
+/// Every method has an empty default, `()` can be used to run the app
+/// without any game logic.
+pub trait Game {
+    /// Called zero or more times per frame with a fixed `dt`, set with
+    /// `XAppBuilder::fixed_timestep`, so game logic run at the same rate
+    /// whatever the frame rate is.
+    fn update(&mut self, _app: &mut XApp, _dt: f32) {}
+
+    /// Called once per frame before drawing. `alpha` is how far the frame
+    /// is between the last `update` and the next one, in `0.0..1.0`, used
+    /// to interpolate motion between two updates.
+    fn render(&mut self, _app: &mut XApp, _alpha: f32) {}
+
+    /// Called after the GPU device was lost, e.g. on driver reset, and the
+    /// app recreated from its builder options. Everything added after
+    /// build, objects, textures, instances and settings, is gone and should
+    /// be set again here.
+    fn device_restored(&mut self, _app: &mut XApp) {}
+
+    /// Called when a key is pressed.
+    fn key(&mut self, _code: Keycode) {}
+
+    /// Called when the close button of window `window_id` is pressed,
+    /// the main window or one added with `XApp::add_window`. Return
+    /// `false` to keep it open, e.g. to ask for saving first. Closing the
+    /// main window hide it while added windows are open, the app quit when
+    /// the last window is closed.
+    fn close_requested(&mut self, _app: &mut XApp, _window_id: u32) -> bool {
+        true
+    }
+}
+
+impl Game for () {}
+
+/// Wrap a closure as a `Game`, used by `XApp::run_with`.
+pub(crate) struct FrameFn<F>(pub(crate) F);
+
+impl<F: FnMut(&mut XApp, f32)> Game for FrameFn<F> {
+    fn render(&mut self, app: &mut XApp, _alpha: f32) {
+        let dt = app.frame_time();
+        (self.0)(app, dt);
+    }
+}
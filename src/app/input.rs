@@ -0,0 +1,256 @@
+use std::collections::{HashMap, HashSet};
+
+use sdl2::{
+    event::{Event, WindowEvent},
+    keyboard::{Keycode, Mod},
+    mouse::MouseButton,
+};
+
+/// Input state collected from SDL events.
+///
+/// Per-frame values (`mouse_delta`, `wheel`) are reset by `begin_frame`,
+/// which `XApp::run` calls before polling events.
+#[derive(Debug)]
+pub struct InputState {
+    /// In pixel, see `pixel_scale`.
+    mouse_position: (i32, i32),
+    /// In window point, as reported by SDL.
+    mouse_position_logical: (i32, i32),
+    /// Pixels per window point, set by `XApp` on resize.
+    pixel_scale: f32,
+    mouse_delta: (i32, i32),
+    wheel: f32,
+    mouse_buttons: HashSet<MouseButton>,
+    keys: HashSet<Keycode>,
+    /// Modifiers of the last key event.
+    mods: Mod,
+    touches: HashMap<i64, (f32, f32)>,
+    relative_mouse: bool,
+    text_input: bool,
+    /// Text typed since the last `take_text_input`.
+    text: String,
+    /// IME composition not committed yet.
+    composition: String,
+}
+
+impl Default for InputState {
+    fn default() -> Self {
+        InputState {
+            mouse_position: (0, 0),
+            mouse_position_logical: (0, 0),
+            pixel_scale: 1.0,
+            mouse_delta: (0, 0),
+            wheel: 0.0,
+            mouse_buttons: HashSet::new(),
+            keys: HashSet::new(),
+            mods: Mod::NOMOD,
+            touches: HashMap::new(),
+            relative_mouse: false,
+            text_input: false,
+            text: String::new(),
+            composition: String::new(),
+        }
+    }
+}
+
+impl InputState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn begin_frame(&mut self) {
+        self.mouse_delta = (0, 0);
+        self.wheel = 0.0;
+    }
+
+    /// `drawable_size` is used to convert normalized touch position to
+    /// pixel, the pixel scale to convert mouse position in window point to
+    /// pixel.
+    pub(crate) fn handle_event(&mut self, event: &Event, drawable_size: (u32, u32)) {
+        let to_pixel = |x: f32, y: f32| (x * drawable_size.0 as f32, y * drawable_size.1 as f32);
+        let pixel_scale = self.pixel_scale;
+        let scale = |x: i32| (x as f32 * pixel_scale).round() as i32;
+        match *event {
+            Event::MouseMotion {
+                x, y, xrel, yrel, ..
+            } => {
+                // cursor is hidden and doesn't move in relative mode
+                if !self.relative_mouse {
+                    self.mouse_position = (scale(x), scale(y));
+                    self.mouse_position_logical = (x, y);
+                }
+                self.mouse_delta.0 += scale(xrel);
+                self.mouse_delta.1 += scale(yrel);
+            }
+            Event::MouseButtonDown { mouse_btn, .. } => {
+                self.mouse_buttons.insert(mouse_btn);
+            }
+            Event::MouseButtonUp { mouse_btn, .. } => {
+                self.mouse_buttons.remove(&mouse_btn);
+            }
+            Event::MouseWheel { precise_y, .. } => {
+                self.wheel += precise_y;
+            }
+            Event::KeyDown {
+                keycode,
+                keymod,
+                ..
+            } => {
+                self.keys.extend(keycode);
+                self.mods = keymod;
+            }
+            Event::KeyUp {
+                keycode,
+                keymod,
+                ..
+            } => {
+                if let Some(key) = keycode {
+                    self.keys.remove(&key);
+                }
+                self.mods = keymod;
+            }
+            Event::FingerDown {
+                finger_id, x, y, ..
+            }
+            | Event::FingerMotion {
+                finger_id, x, y, ..
+            } => {
+                self.touches.insert(finger_id, to_pixel(x, y));
+            }
+            Event::FingerUp { finger_id, .. } => {
+                self.touches.remove(&finger_id);
+            }
+            Event::TextInput { ref text, .. } if self.text_input => {
+                self.text.push_str(text);
+                self.composition.clear();
+            }
+            Event::TextEditing { ref text, .. } if self.text_input => {
+                self.composition.clone_from(text);
+            }
+            // release events are not received while unfocused, forget
+            // everything held to avoid stuck keys
+            Event::Window {
+                win_event: WindowEvent::FocusLost,
+                ..
+            } => {
+                self.keys.clear();
+                self.mods = Mod::NOMOD;
+                self.mouse_buttons.clear();
+                self.touches.clear();
+            }
+            _ => {}
+        }
+    }
+
+    /// Last known mouse position in window pixel, the framebuffer space
+    /// used for picking against what is drawn.
+    pub fn mouse_position(&self) -> (i32, i32) {
+        self.mouse_position
+    }
+
+    /// Last known mouse position in window point, as SDL report it. Same
+    /// as `mouse_position` unless the display is HiDPI.
+    pub fn mouse_position_logical(&self) -> (i32, i32) {
+        self.mouse_position_logical
+    }
+
+    /// Pixels per window point used to convert mouse position, see
+    /// `XApp::pixel_scale`.
+    pub fn pixel_scale(&self) -> f32 {
+        self.pixel_scale
+    }
+
+    /// Set on resize, the window may have moved to a display of another
+    /// pixel density. The last mouse position is converted again.
+    pub(crate) fn set_pixel_scale(&mut self, pixel_scale: f32) {
+        self.pixel_scale = pixel_scale;
+        let (x, y) = self.mouse_position_logical;
+        self.mouse_position = (
+            (x as f32 * pixel_scale).round() as i32,
+            (y as f32 * pixel_scale).round() as i32,
+        );
+    }
+
+    pub(crate) fn set_relative_mouse(&mut self, on: bool) {
+        self.relative_mouse = on;
+    }
+
+    pub(crate) fn set_text_input(&mut self, on: bool) {
+        self.text_input = on;
+        if !on {
+            self.composition.clear();
+        }
+    }
+
+    /// Whether text entry is active, see `XApp::set_text_input`.
+    pub fn is_text_input(&self) -> bool {
+        self.text_input
+    }
+
+    /// Return UTF-8 text typed since the last call and clear it.
+    pub fn take_text_input(&mut self) -> String {
+        std::mem::take(&mut self.text)
+    }
+
+    /// Text being composed with the IME, replaced by the final text in
+    /// `take_text_input` once committed. Empty without IME.
+    pub fn text_editing(&self) -> &str {
+        &self.composition
+    }
+
+    /// Whether the mouse is captured, see `XApp::set_relative_mouse`.
+    pub fn is_relative_mouse(&self) -> bool {
+        self.relative_mouse
+    }
+
+    /// Mouse movement in pixel since the start of this frame.
+    pub fn mouse_delta(&self) -> (i32, i32) {
+        self.mouse_delta
+    }
+
+    /// Vertical wheel scroll since the start of this frame.
+    /// Positive value means scrolling away from the user.
+    pub fn wheel(&self) -> f32 {
+        self.wheel
+    }
+
+    pub fn is_mouse_button_down(&self, button: MouseButton) -> bool {
+        self.mouse_buttons.contains(&button)
+    }
+
+    /// Whether `key` is currently held.
+    pub fn is_key_down(&self, key: Keycode) -> bool {
+        self.keys.contains(&key)
+    }
+
+    /// Modifier keys held, as of the last key event. Lock keys like
+    /// `CAPSMOD` and `NUMMOD` are included.
+    pub fn mods(&self) -> Mod {
+        self.mods
+    }
+
+    /// Whether either Shift key is held.
+    pub fn is_shift_down(&self) -> bool {
+        self.mods.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD)
+    }
+
+    /// Whether either Ctrl key is held.
+    pub fn is_ctrl_down(&self) -> bool {
+        self.mods.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD)
+    }
+
+    /// Whether either Alt key is held.
+    pub fn is_alt_down(&self) -> bool {
+        self.mods.intersects(Mod::LALTMOD | Mod::RALTMOD)
+    }
+
+    /// Pixel position of finger `finger_id` while it touches the screen.
+    pub fn touch(&self, finger_id: i64) -> Option<(f32, f32)> {
+        self.touches.get(&finger_id).copied()
+    }
+
+    /// Finger id and pixel position of every finger touching the screen.
+    pub fn touches(&self) -> impl Iterator<Item = (i64, (f32, f32))> + '_ {
+        self.touches.iter().map(|(id, pos)| (*id, *pos))
+    }
+}
@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use wgpu::{BindGroupLayout, BindGroupLayoutEntry, Device};
+
+/// Number of layouts created and reused by the layout cache, see
+/// `XApp::layout_cache_stats`.
+#[cfg(debug_assertions)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LayoutCacheStats {
+    /// Distinct layouts created.
+    pub layouts: usize,
+    /// Requests answered with an existing layout.
+    pub hits: usize,
+}
+
+/// Bind group layouts keyed by their entries, so identical layouts are
+/// created once and shared.
+#[derive(Default)]
+pub(crate) struct LayoutCache {
+    layouts: HashMap<Vec<BindGroupLayoutEntry>, Arc<BindGroupLayout>>,
+    #[cfg(debug_assertions)]
+    hits: usize,
+}
+
+impl LayoutCache {
+    /// Layout with `entries`, created on first request. The label of the
+    /// first request is kept.
+    pub(crate) fn get(
+        &mut self,
+        device: &Device,
+        label: &str,
+        entries: &[BindGroupLayoutEntry],
+    ) -> Arc<BindGroupLayout> {
+        if let Some(layout) = self.layouts.get(entries) {
+            #[cfg(debug_assertions)]
+            {
+                self.hits += 1;
+            }
+            return layout.clone();
+        }
+        let layout = Arc::new(
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some(label),
+                entries,
+            }),
+        );
+        self.layouts.insert(entries.to_vec(), layout.clone());
+        layout
+    }
+
+    #[cfg(debug_assertions)]
+    pub(crate) fn stats(&self) -> LayoutCacheStats {
+        LayoutCacheStats {
+            layouts: self.layouts.len(),
+            hits: self.hits,
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use wgpu::BindGroupLayoutEntry;
+
+/// Directional light data, matches `Light` in shader.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct LightUniform {
+    /// Direction the light travel to, normalized.
+    direction: [f32; 3],
+    _padding: f32,
+    color: [f32; 3],
+    ambient: f32,
+}
+
+impl LightUniform {
+    /// Light traveling into the screen like the camera looks, toward
+    /// `+z`, so geometry facing the camera keep its vertex color.
+    pub(crate) fn new() -> Self {
+        LightUniform {
+            direction: [0.0, 0.0, 1.0],
+            _padding: 0.0,
+            color: [1.0; 3],
+            ambient: 0.0,
+        }
+    }
+
+    pub(crate) fn set_light(&mut self, direction: [f32; 3], color: [f32; 3]) {
+        let [x, y, z] = direction;
+        let length = (x * x + y * y + z * z).sqrt();
+        if length > 0.0 {
+            self.direction = [x / length, y / length, z / length];
+        }
+        self.color = color;
+    }
+
+    pub(crate) fn set_ambient(&mut self, ambient: f32) {
+        self.ambient = ambient;
+    }
+}
+
+/// Bind group layout of the light uniform.
+pub(crate) const LIGHT_LAYOUT_ENTRIES: [BindGroupLayoutEntry; 1] = [BindGroupLayoutEntry {
+    binding: 0,
+    visibility: wgpu::ShaderStages::FRAGMENT,
+    ty: wgpu::BindingType::Buffer {
+        ty: wgpu::BufferBindingType::Uniform,
+        has_dynamic_offset: false,
+        min_binding_size: None,
+    },
+    count: None,
+}];
+
+#[cfg(test)]
+mod tests {
+    use super::super::validate::check_winding;
+    use super::super::{compute_normals, Camera2D, Projection, Vertex};
+    use super::*;
+
+    /// Diffuse term of `fs_main` in `shader.wgsl`.
+    fn lambert(light: &LightUniform, normal: [f32; 3]) -> f32 {
+        let d = light.direction;
+        (-(normal[0] * d[0] + normal[1] * d[1] + normal[2] * d[2])).max(0.0)
+    }
+
+    #[test]
+    fn face_toward_camera_is_lit() {
+        let mut vertices: Vec<Vertex> = [[-0.5, -0.5, 0.0], [0.5, -0.5, 0.0], [0.0, 0.5, 0.0]]
+            .map(|position| Vertex {
+                position,
+                color: [1.0; 3],
+                normal: [0.0; 3],
+            })
+            .to_vec();
+        let indices = [0, 1, 2];
+        let mut camera = Camera2D::new(800, 600);
+        camera.set_projection(Projection::perspective());
+        let report = check_winding(
+            &vertices,
+            &indices,
+            &camera.view_proj(),
+            wgpu::FrontFace::Ccw,
+            Some(wgpu::Face::Back),
+        );
+        assert_eq!(report.front_facing, 1);
+
+        compute_normals(&mut vertices, &indices);
+        let light = LightUniform::new();
+        for vertex in &vertices {
+            // the camera is at negative z looking toward +z
+            assert!(vertex.normal[2] < 0.0, "{:?}", vertex.normal);
+            assert!(lambert(&light, vertex.normal) > 0.99);
+        }
+        // seen from behind it is dark
+        assert_eq!(lambert(&light, [0.0, 0.0, 1.0]), 0.0);
+    }
+}
@@ -0,0 +1,141 @@
+use std::{
+    cell::Cell,
+    path::PathBuf,
+    sync::mpsc::{self, Receiver, Sender},
+    thread::JoinHandle,
+};
+
+use log::warn;
+use sdl2::{pixels::PixelFormatEnum, rwops::RWops, surface::Surface};
+
+use super::{obj, Vertex};
+
+/// Texture requested with `XApp::request_texture`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TextureHandle(pub(crate) usize);
+
+/// Mesh requested with `XApp::request_mesh`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MeshHandle(pub(crate) usize);
+
+enum Job {
+    Texture(usize, PathBuf),
+    Mesh(usize, PathBuf),
+}
+
+/// CPU data decoded by the loader thread, uploaded by the main thread.
+pub(crate) enum Loaded {
+    /// Width, height and RGBA8 pixels.
+    Texture(TextureHandle, Result<(u32, u32, Vec<u8>), String>),
+    Mesh(MeshHandle, Result<(Vec<Vertex>, Vec<u32>), String>),
+}
+
+/// Read and decode files on a background thread so loading doesn't stall
+/// the frame.
+pub(crate) struct ResourceLoader {
+    next_handle: Cell<usize>,
+    jobs: Option<Sender<Job>>,
+    loaded: Receiver<Loaded>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ResourceLoader {
+    pub(crate) fn new() -> Self {
+        let (jobs, job_receiver) = mpsc::channel();
+        let (loaded_sender, loaded) = mpsc::channel();
+        let thread = std::thread::Builder::new()
+            .name("resource loader".to_string())
+            .spawn(move || {
+                // end when the loader is dropped
+                for job in job_receiver {
+                    let result = match job {
+                        Job::Texture(handle, path) => {
+                            Loaded::Texture(TextureHandle(handle), decode_bmp_file(&path))
+                        }
+                        Job::Mesh(handle, path) => Loaded::Mesh(
+                            MeshHandle(handle),
+                            read_file(&path).and_then(|bytes| obj::parse_obj(&bytes)),
+                        ),
+                    };
+                    if loaded_sender.send(result).is_err() {
+                        break;
+                    }
+                }
+            })
+            .ok();
+
+        ResourceLoader {
+            next_handle: Cell::new(0),
+            jobs: Some(jobs),
+            loaded,
+            thread,
+        }
+    }
+
+    pub(crate) fn request_texture(&self, path: PathBuf) -> TextureHandle {
+        let handle = self.next();
+        self.send(Job::Texture(handle, path));
+        TextureHandle(handle)
+    }
+
+    pub(crate) fn request_mesh(&self, path: PathBuf) -> MeshHandle {
+        let handle = self.next();
+        self.send(Job::Mesh(handle, path));
+        MeshHandle(handle)
+    }
+
+    /// Resources finished since the last call, never block.
+    pub(crate) fn finished(&self) -> Vec<Loaded> {
+        self.loaded.try_iter().collect()
+    }
+
+    fn next(&self) -> usize {
+        let handle = self.next_handle.get();
+        self.next_handle.set(handle + 1);
+        handle
+    }
+
+    fn send(&self, job: Job) {
+        let sent = self.jobs.as_ref().map(|x| x.send(job).is_ok());
+        if sent != Some(true) {
+            warn!("Resource loader thread is not running");
+        }
+    }
+}
+
+impl Drop for ResourceLoader {
+    fn drop(&mut self) {
+        // closing the channel stop the thread after its current job
+        self.jobs = None;
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn read_file(path: &PathBuf) -> Result<Vec<u8>, String> {
+    std::fs::read(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))
+}
+
+/// Decode a BMP file to RGBA8 with SDL.
+fn decode_bmp_file(path: &PathBuf) -> Result<(u32, u32, Vec<u8>), String> {
+    decode_bmp(&read_file(path)?)
+}
+
+/// Decode BMP `bytes` to RGBA8 with SDL, return width, height and the
+/// tightly packed pixels.
+pub(crate) fn decode_bmp(bytes: &[u8]) -> Result<(u32, u32, Vec<u8>), String> {
+    let surface = Surface::load_bmp_rw(&mut RWops::from_bytes(bytes)?)?;
+    let surface = surface.convert_format(PixelFormatEnum::RGBA32)?;
+    let (width, height) = (surface.width(), surface.height());
+    let pitch = surface.pitch() as usize;
+    let row = width as usize * 4;
+    let pixels = surface.with_lock(|data| {
+        data.chunks(pitch)
+            .take(height as usize)
+            .flat_map(|x| &x[..row])
+            .copied()
+            .collect::<Vec<_>>()
+    });
+    Ok((width, height, pixels))
+}
@@ -1,21 +1,117 @@
 use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
 
 #[cfg(debug_assertions)]
 use log::info;
+#[cfg(debug_assertions)]
+use log::trace;
+use log::warn;
 
-use sdl2::{event::Event, keyboard::Keycode, video::Window, EventPump, Sdl};
+use sdl2::{
+    event::{Event, WindowEvent},
+    keyboard::Keycode,
+    mouse::MouseButton,
+    video::Window,
+    EventPump, Sdl,
+};
 use wgpu::{
-    include_wgsl, util::DeviceExt, Backends, BlendState, ColorWrites, CommandEncoderDescriptor,
-    Device, DeviceDescriptor, Instance, PipelineCompilationOptions, Queue, RenderPipeline,
-    RenderPipelineDescriptor, RequestAdapterOptions, Surface, SurfaceConfiguration,
-    SurfaceTargetUnsafe, TextureFormat,
+    util::{DeviceExt, StagingBelt},
+    Backends, BlendState, CommandEncoderDescriptor, DepthStencilState, Device, DeviceDescriptor,
+    Features, Instance, PipelineLayout, PolygonMode, PresentMode, PrimitiveTopology, Queue,
+    RenderPipeline, RequestAdapterOptions, ShaderModule, Surface, SurfaceConfiguration,
+    SurfaceTargetUnsafe, TextureFormat, TextureView,
+};
+
+mod adapter;
+mod blit;
+mod builder;
+mod camera;
+mod clock;
+mod compute;
+mod controller;
+mod cubemap;
+mod debug_lines;
+mod fps;
+mod frame;
+mod frustum;
+mod game;
+mod input;
+mod layout;
+mod light;
+mod loader;
+mod obj;
+mod object;
+mod orbit;
+mod particle;
+mod pass;
+mod pipeline;
+mod post;
+mod skybox;
+mod sprite;
+mod surface;
+mod text;
+mod texture;
+mod timer;
+mod validate;
+mod window_state;
+
+use compute::ComputeAnimator;
+
+use adapter::AdapterSelection;
+pub use adapter::AdapterSummary;
+use blit::Blitter;
+pub use builder::XAppBuilder;
+pub use camera::{Camera2D, Projection};
+pub use clock::{Clock, MockClock, SystemClock};
+use controller::Controllers;
+pub use cubemap::CubeTarget;
+use debug_lines::DebugLines;
+pub use fps::FpsCounter;
+use frame::FrameRing;
+use frustum::Frustum;
+use game::FrameFn;
+pub use game::Game;
+pub use input::InputState;
+use layout::LayoutCache;
+#[cfg(debug_assertions)]
+pub use layout::LayoutCacheStats;
+use light::LightUniform;
+use loader::{Loaded, ResourceLoader};
+pub use loader::{MeshHandle, TextureHandle};
+pub use obj::compute_normals;
+use object::Indices;
+pub use object::{RenderObject, IDENTITY_MATRIX};
+pub use orbit::OrbitCamera;
+pub use particle::{Particle, ParticleSettings, ParticleSystem, MAX_PARTICLES};
+pub use pass::{
+    FramePass, PassContext, ScenePass, COMPUTE_PASS_ORDER, DEFAULT_CLEAR_COLOR, POST_PASS_ORDER,
+    SCENE_PASS_ORDER, TEXT_PASS_ORDER,
 };
+use pass::{PassKind, PassList};
+use pipeline::DepthPrepass;
+pub use post::OffscreenTarget;
+use post::PostProcess;
+use skybox::Skybox;
+use sprite::SpriteBatch;
+pub use surface::{SurfaceCapabilitiesSummary, SurfaceSelection};
+use surface::{SurfaceSource, WindowSurface};
+use text::TextRenderer;
+use texture::SamplerCache;
+pub use texture::{SamplerOptions, Texture};
+use timer::GpuTimer;
+pub use validate::WindingReport;
+use window_state::WindowState;
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-struct Vertex {
-    position: [f32; 3],
-    color: [f32; 3],
+pub struct Vertex {
+    pub position: [f32; 3],
+    pub color: [f32; 3],
+    /// Surface direction used for lighting, `[0.0, 0.0, -1.0]` face the
+    /// default camera, which looks toward `+z`.
+    pub normal: [f32; 3],
 }
 
 impl Vertex {
@@ -34,6 +130,62 @@ impl Vertex {
                     shader_location: 1,
                     format: wgpu::VertexFormat::Float32x3,
                 },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+            ],
+        }
+    }
+}
+
+/// Per-instance data. Every object is drawn once for each instance,
+/// with the instance transform applied before the object model matrix.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct InstanceRaw {
+    pub model: [[f32; 4]; 4],
+}
+
+impl InstanceRaw {
+    pub fn new(position: [f32; 3], scale: f32) -> Self {
+        InstanceRaw {
+            model: [
+                [scale, 0.0, 0.0, 0.0],
+                [0.0, scale, 0.0, 0.0],
+                [0.0, 0.0, scale, 0.0],
+                [position[0], position[1], position[2], 1.0],
+            ],
+        }
+    }
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        // a mat4x4 takes 4 vertex slots, one for each column
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<InstanceRaw>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 5,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                    shader_location: 6,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
+                    shader_location: 7,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 12]>() as wgpu::BufferAddress,
+                    shader_location: 8,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
             ],
         }
     }
@@ -43,85 +195,299 @@ const VERTICES: &[Vertex] = &[
     Vertex {
         position: [-0.5, 0.5, 0.0],
         color: [1.0, 0.0, 0.0],
+        normal: [0.0, 0.0, -1.0],
     },
     Vertex {
         position: [-0.5, -0.5, 0.0],
         color: [0.0, 1.0, 0.0],
+        normal: [0.0, 0.0, -1.0],
     },
     Vertex {
         position: [0.5, -0.5, 0.0],
         color: [0.0, 0.0, 1.0],
+        normal: [0.0, 0.0, -1.0],
     },
     Vertex {
         position: [0.6, 0.6, 0.0],
         color: [1.0, 1.0, 0.0],
+        normal: [0.0, 0.0, -1.0],
     },
     Vertex {
         position: [-0.4, 0.6, 0.0],
         color: [0.0, 1.0, 1.0],
+        normal: [0.0, 0.0, -1.0],
     },
     Vertex {
         position: [0.6, -0.4, 0.0],
         color: [1.0, 0.0, 1.0],
+        normal: [0.0, 0.0, -1.0],
     },
 ];
 
+const INDICES: &[u16] = &[0, 1, 2, 3, 4, 5];
+
+/// Magenta and black checker, RGBA8.
+const PLACEHOLDER_PIXELS: [u8; 16] = [
+    255, 0, 255, 255, 0, 0, 0, 255, // top row
+    0, 0, 0, 255, 255, 0, 255, 255, // bottom row
+];
+
+/// Vertex colors are blended across each triangle.
+pub const RENDER_MODE_INTERPOLATED: u32 = 0;
+/// Each triangle use the color of one of its vertices.
+pub const RENDER_MODE_FLAT: u32 = 1;
+
+/// Surface size used when window report zero size at startup.
+const DEFAULT_SURFACE_SIZE: (u32, u32) = (1280, 720);
+/// Range of `XApp::set_render_scale`.
+const MIN_RENDER_SCALE: f32 = 0.25;
+const MAX_RENDER_SCALE: f32 = 2.0;
+/// Range of `XAppBuilder::max_frame_latency`, also the number of per-frame
+/// resource sets so the latency can be raised at runtime.
+const MAX_FRAME_LATENCY: u32 = 3;
+/// Longest frame time fed to the fixed timestep accumulator.
+const MAX_FRAME_TIME: f32 = 0.25;
+/// Size of the staging belt buffers, bigger than a frame of uniforms for
+/// a few hundred objects.
+const STAGING_CHUNK_SIZE: wgpu::BufferAddress = 64 * 1024;
+/// Longest wait for an event of an idle on-demand app, resources loaded
+/// in the background are picked up at least this often.
+const IDLE_WAIT_MS: u32 = 100;
+/// Number of 10 ms waits for window to report non zero size.
+const WINDOW_SIZE_RETRIES: u32 = 10;
+
+/// Where frames are drawn to.
+enum RenderTarget<'l> {
+    /// Swapchain of the SDL window.
+    Surface(Surface<'l>),
+    /// Texture used by headless app, no window is shown.
+    Offscreen(wgpu::Texture),
+    /// Surface released before the OS destroy the native window, nothing
+    /// is drawn anymore. Also set when the main window is closed while
+    /// added windows are open, those are still drawn.
+    Released,
+}
+
 pub struct XApp<'l> {
-    sdl_ctx: Sdl,
+    sdl_ctx: Option<Sdl>,
+    /// Builder options of a windowed app, used to recreate it when the
+    /// device is lost.
+    builder: Option<XAppBuilder>,
+    device_lost: Arc<Mutex<Option<String>>>,
+    controllers: Controllers,
     #[cfg(target_os = "android")]
     wgpu_intance: Instance,
-    surface: Surface<'l>,
+    target: RenderTarget<'l>,
     device: Device,
     config: SurfaceConfiguration,
     surface_format: TextureFormat,
+    surface_capabilities: SurfaceCapabilitiesSummary,
+    /// Set on desktop windowed app, see `add_window`.
+    surface_source: Option<SurfaceSource>,
+    /// Windows added with `add_window`.
+    windows: Vec<WindowSurface<'l>>,
+    /// Save window placement on quit, see `XAppBuilder::remember_window_state`.
+    remember_window_state: bool,
     queue: Queue,
+    adapter: AdapterSummary,
+    /// Discrete GPU found at init when `adapter` is another kind, see
+    /// `discrete_adapter`.
+    discrete_adapter: Option<AdapterSummary>,
     pipeline: RenderPipeline,
+    alpha_pipeline: RenderPipeline,
+    mask_pipeline: RenderPipeline,
+    wireframe_pipeline: Option<RenderPipeline>,
+    depth_prepass: Option<DepthPrepass>,
+    /// Opaque and transparent pipelines of topologies other than
+    /// `TriangleList`, created by `set_topology` on first use.
+    topology_pipelines: HashMap<PrimitiveTopology, [RenderPipeline; 2]>,
+    scene_shader: ShaderModule,
+    scene_pipeline_layout: PipelineLayout,
+    scene_depth_stencil: DepthStencilState,
+    /// Front face and cull mode from the builder, topology pipelines
+    /// only change the topology.
+    scene_primitive: wgpu::PrimitiveState,
+    wireframe: bool,
+    draw_geometry: bool,
+    scene_pass: ScenePass,
+    passes: RefCell<PassList>,
+    render_scale: f32,
+    depth_stencil_view: wgpu::TextureView,
+    stencil_reference: u32,
+    push_constants: bool,
+    compute: Option<ComputeAnimator>,
+    post_process: Option<PostProcess>,
+    blitter: Blitter,
+    text: Option<TextRenderer>,
+    gpu_timer: Option<GpuTimer>,
     // event_pump: EventPump,
-    window: Window,
+    window: Option<Window>,
     window_height: u32,
     window_width: u32,
+    title: String,
+    fps_counter: FpsCounter,
+    clock: Box<dyn Clock>,
+    show_fps_in_title: bool,
+    debug_overlay: bool,
+    show_overlay: bool,
+    frame_time: f32,
+    fixed_timestep: f32,
+    target_fps: Option<u32>,
+    /// See `XAppBuilder::on_demand`.
+    on_demand: bool,
+    /// Check the winding of loaded meshes on debug build.
+    check_winding: bool,
+    /// Something changed since the last drawn frame, only checked
+    /// `on_demand`.
+    needs_redraw: bool,
+    focused: bool,
+    minimized: bool,
+    paused: bool,
+    /// Set on unpause, the next frame start with a zero dt.
+    reset_frame_timer: bool,
+    /// Last size of this frame `SizeChanged` events, see `queue_resize`.
+    pending_resize: Option<(u32, u32)>,
+
+    model_bind_group_layout: wgpu::BindGroupLayout,
+    /// Camera and object uniforms, one set per frame in flight.
+    frames: FrameRing,
+    objects: Vec<RenderObject>,
+
+    instance_buffer: wgpu::Buffer,
+    instance_count: u32,
+    /// Instances set since the last frame, uploaded by the staging belt.
+    pending_instances: Cell<Option<Vec<u8>>>,
+    /// Matrix of the only instance, `None` when there are several and
+    /// frustum culling is skipped.
+    single_instance: Option<[[f32; 4]; 4]>,
+    frustum_culling: bool,
+    /// Objects skipped by frustum culling in the last scene pass.
+    culled: Cell<u32>,
 
-    vertex_buffer: wgpu::Buffer,
+    camera: Camera2D,
+    /// Replace `camera` in the camera uniform when set.
+    orbit_camera: Option<OrbitCamera>,
+    render_mode: u32,
+    render_mode_buffer: wgpu::Buffer,
+    /// Scene shader gamma encode its output, set for linear targets.
+    gamma_correction: bool,
+    light: LightUniform,
+    light_buffer: wgpu::Buffer,
+    /// Light changed since the last frame, uploaded by the staging belt.
+    light_dirty: Cell<bool>,
+    /// See `XAppBuilder::staging_belt`, `None` use `Queue::write_buffer`.
+    staging_belt: Option<RefCell<StagingBelt>>,
+    light_bind_group: wgpu::BindGroup,
+    input: InputState,
+
+    texture_layout: Arc<wgpu::BindGroupLayout>,
+    layouts: LayoutCache,
+    samplers: SamplerCache,
+    /// Sampler of textures requested with `request_texture_with_sampler`.
+    requested_samplers: HashMap<TextureHandle, SamplerOptions>,
+    textures: Vec<Texture>,
+    sprites: SpriteBatch,
+    particles: ParticleSystem,
+    debug_lines: DebugLines,
+    skybox: Option<Skybox>,
+
+    loader: ResourceLoader,
+    placeholder_texture: Option<usize>,
+    loaded_textures: HashMap<TextureHandle, usize>,
+    loaded_meshes: HashMap<MeshHandle, usize>,
 }
 
 impl<'l> XApp<'l> {
     pub fn new(window_title: &str) -> Result<Self, String> {
-        // Init env_logger to show wgpu log error
-        #[cfg(debug_assertions)]
-        env_logger::init();
+        XAppBuilder::new(window_title).build()
+    }
+
+    /// Create app without window which draws into an offscreen texture.
+    /// Frames are produced with `render_frame` and read back with `capture`,
+    /// the event loop (`run`) is not available.
+    pub fn new_headless(width: u32, height: u32) -> Result<Self, String> {
+        XAppBuilder::new("").build_headless(width, height)
+    }
+
+    pub fn builder(window_title: &str) -> XAppBuilder {
+        XAppBuilder::new(window_title)
+    }
+
+    pub(crate) fn with_window(builder: &XAppBuilder) -> Result<Self, String> {
+        init_logger(builder);
 
         // Init SDL2
         let sdl_ctx = sdl2::init()?;
         #[cfg(target_os = "android")]
         sdl2::hint::set("SDL_VIDEO_EXTERNAL_CONTEXT", "1");
+        // closing the last window send only its Close event, so the game
+        // can keep it open, see `Game::close_requested`
+        sdl2::hint::set("SDL_QUIT_ON_LAST_WINDOW_CLOSE", "0");
         let sdl_video_subsystem = sdl_ctx.video()?;
+        // SDL start text input on desktop, wait for `set_text_input`
+        sdl_video_subsystem.text_input().stop();
         // let event_pump = sdl_ctx.event_pump()?;
 
-        let window = sdl_video_subsystem
-            .window(window_title, 0, 0)
-            .fullscreen()
-            .position_centered()
+        let display = select_display(&sdl_video_subsystem, builder.display)?;
+        let bounds = sdl_video_subsystem.display_bounds(display)?;
+        let mut window_builder = match builder.windowed {
+            Some((w, h)) => sdl_video_subsystem.window(&builder.title, w, h),
+            // zero size use the display mode
+            None => {
+                let mut x = sdl_video_subsystem.window(&builder.title, 0, 0);
+                x.fullscreen();
+                x
+            }
+        };
+        match (builder.position, builder.windowed) {
+            (Some((x, y)), _) => window_builder.position(bounds.x() + x, bounds.y() + y),
+            (None, Some((w, h))) => window_builder.position(
+                bounds.x() + (bounds.width() as i32 - w as i32) / 2,
+                bounds.y() + (bounds.height() as i32 - h as i32) / 2,
+            ),
+            // fullscreen go to the display containing the window
+            (None, None) => window_builder.position(bounds.x(), bounds.y()),
+        };
+        if is_transparent(builder.alpha_mode) {
+            window_builder.borderless();
+        }
+        let mut window = window_builder
             .allow_highdpi()
             .build()
             .map_err(|e| e.to_string())?;
-        let (w, h) = window.size();
-
-        //create instance
-        let backend = {
-            #[cfg(debug_assertions)]
-            {
-                info!("Targeting debug use backend: Secondary");
-                Backends::SECONDARY
+        if let Some(icon) = &builder.icon {
+            match load_icon(icon) {
+                Ok(surface) => window.set_icon(surface),
+                Err(e) => warn!("Cannot load window icon: {}", e),
             }
+        }
 
-            #[cfg(not(debug_assertions))]
-            {
-                Backends::PRIMARY
+        // Some window managers (and android during rotation) report zero
+        // size right after creation, wait a bit for the real size.
+        let mut size = drawable_size(&window);
+        for _ in 0..WINDOW_SIZE_RETRIES {
+            if size.0 != 0 || size.1 != 0 {
+                break;
             }
-        };
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            size = drawable_size(&window);
+        }
+        let logical = window.size();
+        #[cfg(debug_assertions)]
+        info!("Window size {:?}, drawable size {:?}", logical, size);
+        // HiDPI display has more pixels than points, never less. A zero
+        // dimension is transient and clamped below.
+        if size.0 != 0 && size.1 != 0 && (size.0 < logical.0 || size.1 < logical.1) {
+            warn!(
+                "Drawable size {:?} smaller than window size {:?}",
+                size, logical
+            );
+        }
+        let (w, h) = clamp_surface_size(size.0, size.1);
 
+        //create instance
         let instance = Instance::new(wgpu::InstanceDescriptor {
-            backends: backend,
+            backends: select_backends(builder),
             gles_minor_version: wgpu::Gles3MinorVersion::Version0,
             ..Default::default()
         });
@@ -137,17 +503,7 @@ impl<'l> XApp<'l> {
 
         // get adapter
         let adapter = {
-            let adapter_option = RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
-            };
-
-            let adapter = pollster::block_on(instance.request_adapter(&adapter_option));
-            let adapter = match adapter {
-                Some(x) => x,
-                None => return Err("Cannot get adapter".to_string()),
-            };
+            let adapter = request_adapter(&instance, builder, Some(&surface))?;
 
             #[cfg(debug_assertions)]
             {
@@ -156,11 +512,11 @@ impl<'l> XApp<'l> {
                 let limits = adapter.limits();
                 let info = adapter.get_info();
 
-                info!("Adapter: ");
-                info!(" - Info: {:?}", info);
-                info!(" - Adapter limits: {:?}", limits);
-                info!(" - Features {:?}", features);
-                info!(" - Down level capacity: {:?}", down_level_capa);
+                trace!("Adapter: ");
+                trace!(" - Info: {:?}", info);
+                trace!(" - Adapter limits: {:?}", limits);
+                trace!(" - Features {:?}", features);
+                trace!(" - Down level capacity: {:?}", down_level_capa);
             }
             adapter
         };
@@ -175,47 +531,35 @@ impl<'l> XApp<'l> {
                 let present_modes = &surface_capability.present_modes;
                 let usage = &surface_capability.usages;
 
-                info!("Surface Capabilities:");
-                info!(" - Texture format:");
+                trace!("Surface Capabilities:");
+                trace!(" - Texture format:");
                 for (i, tf) in texture_formats.iter().enumerate() {
-                    info!("  {}. {:?}", i, tf);
+                    trace!("  {}. {:?}", i, tf);
                 }
 
-                info!(" - Alpha Mode:");
+                trace!(" - Alpha Mode:");
                 for (i, am) in alpha_modes.iter().enumerate() {
-                    info!("  {}. {:?}", i, am);
+                    trace!("  {}. {:?}", i, am);
                 }
 
-                info!(" - Present Mode:");
+                trace!(" - Present Mode:");
                 for (i, pm) in present_modes.iter().enumerate() {
-                    info!("  {}. {:?}", i, pm);
+                    trace!("  {}. {:?}", i, pm);
                 }
 
-                info!(" - Usage: {:?}", usage);
+                trace!(" - Usage: {:?}", usage);
             }
             surface_capability
         };
-        let surface_format = surface_capabilities
-            .formats
-            .iter()
-            .copied()
-            .find(|f| f.is_srgb())
-            .unwrap_or(surface_capabilities.formats[0]);
+        let surface_format = select_surface_format(&surface_capabilities.formats, builder)?;
+        let view_format = srgb_view_format(&adapter, surface_format, builder);
 
         // get device and queue
-        let (device, queue) = {
-            let lim = adapter.limits();
-            let device_desc = DeviceDescriptor {
-                label: Some("Xapp Device"),
-                required_limits: lim,
-                ..Default::default()
-            };
-            let device = pollster::block_on(adapter.request_device(&device_desc, None));
-            match device.map_err(|e| e.to_string()) {
-                Ok((x, y)) => (x, y),
-                Err(e) => return Err(e),
-            }
-        };
+        // builder option resolved against adapter support
+        let mut options = builder.clone();
+        options.compute = builder.compute && compute_supported(&adapter);
+        options.max_anisotropy = max_anisotropy(&adapter, builder.max_anisotropy);
+        let (device, queue) = request_device(&adapter, options.compute)?;
 
         // create config
         let config = wgpu::SurfaceConfiguration {
@@ -224,196 +568,3319 @@ impl<'l> XApp<'l> {
             width: w,
             height: h,
             present_mode: wgpu::PresentMode::Fifo,
-            alpha_mode: wgpu::CompositeAlphaMode::Auto,
-            view_formats: Vec::default(),
-            desired_maximum_frame_latency: 2,
+            alpha_mode: select_alpha_mode(&surface_capabilities.alpha_modes, builder.alpha_mode),
+            view_formats: view_format.into_iter().collect(),
+            desired_maximum_frame_latency: clamp_frame_latency(builder.max_frame_latency),
         };
+        #[cfg(debug_assertions)]
+        info!(
+            "Maximum frame latency: {}",
+            config.desired_maximum_frame_latency
+        );
         // run surface configuration
         surface.configure(&device, &config);
 
+        let mut app = Self::from_parts(
+            Some((sdl_ctx, window)),
+            #[cfg(target_os = "android")]
+            instance,
+            RenderTarget::Surface(surface),
+            device,
+            queue,
+            config,
+            AdapterSummary::from(&adapter.get_info()),
+            &options,
+        )?;
+        app.surface_capabilities = surface_capabilities.into();
+        #[cfg(not(target_os = "android"))]
+        {
+            app.surface_source = Some(SurfaceSource { instance, adapter });
+        }
+        app.remember_window_state = builder.remember_window_state;
+        app.builder = Some(builder.clone());
+        app.discrete_adapter = find_discrete_adapter(&app.adapter);
+        sdl2::log::log(&app.init_summary());
+        Ok(app)
+    }
+
+    pub(crate) fn headless(builder: &XAppBuilder, width: u32, height: u32) -> Result<Self, String> {
+        init_logger(builder);
+
+        let (width, height) = clamp_surface_size(width, height);
+
+        let instance = Instance::new(wgpu::InstanceDescriptor {
+            backends: select_backends(builder),
+            gles_minor_version: wgpu::Gles3MinorVersion::Version0,
+            ..Default::default()
+        });
+
+        let adapter = request_adapter(&instance, builder, None)?;
+        #[cfg(debug_assertions)]
+        info!("Headless adapter: {:?}", adapter.get_info());
+
+        // builder option resolved against adapter support
+        let mut options = builder.clone();
+        options.compute = builder.compute && compute_supported(&adapter);
+        options.max_anisotropy = max_anisotropy(&adapter, builder.max_anisotropy);
+        let (device, queue) = request_device(&adapter, options.compute)?;
+
+        // surface configuration is kept to describe the offscreen target
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            format: if builder.linear_format {
+                TextureFormat::Rgba8Unorm
+            } else {
+                TextureFormat::Rgba8UnormSrgb
+            },
+            width,
+            height,
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            view_formats: Vec::default(),
+            desired_maximum_frame_latency: clamp_frame_latency(builder.max_frame_latency),
+        };
+        let texture = create_offscreen_texture(&device, &config);
+
+        let app = Self::from_parts(
+            None,
+            #[cfg(target_os = "android")]
+            instance,
+            RenderTarget::Offscreen(texture),
+            device,
+            queue,
+            config,
+            AdapterSummary::from(&adapter.get_info()),
+            &options,
+        )?;
+        sdl2::log::log(&app.init_summary());
+        Ok(app)
+    }
+
+    /// Build pipeline and scene resources shared by every constructor.
+    /// Invalid shader or pipeline is returned as error instead of panicking.
+    fn from_parts(
+        window: Option<(Sdl, Window)>,
+        #[cfg(target_os = "android")] instance: Instance,
+        target: RenderTarget<'l>,
+        device: Device,
+        queue: Queue,
+        config: SurfaceConfiguration,
+        adapter: AdapterSummary,
+        options: &XAppBuilder,
+    ) -> Result<Self, String> {
+        let (sdl_ctx, window) = window.unzip();
+        // catch shader compilation, pipeline and other resource validation
+        // errors
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        // pipelines target the sRGB view of a linear surface when there
+        // is one
+        let surface_format = config
+            .view_formats
+            .first()
+            .copied()
+            .unwrap_or(config.format);
+        let (w, h) = (config.width, config.height);
+
         //create pipe line
-        let shader = device.create_shader_module(include_wgsl!("shader.wgsl"));
+        let push_constants = device.features().contains(Features::PUSH_CONSTANTS);
+        #[cfg(debug_assertions)]
+        info!("Per-draw data use push constants: {}", push_constants);
+        let shader = pipeline::create_scene_shader(&device, push_constants);
+        let model_bind_group_layout = object::model_bind_group_layout(&device);
+
+        // camera uniform
+        let camera = Camera2D::new(w, h);
+        // linear target is gamma corrected by the scene shader, so colors
+        // match an sRGB target
+        let gamma_correction = !surface_format.is_srgb();
+        #[cfg(debug_assertions)]
+        info!("Shader gamma correction: {}", gamma_correction);
+        // padded to 16 bytes, the minimum uniform size on some backends
+        let render_mode_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("render mode uniform"),
+            contents: bytemuck::cast_slice(&render_mode_uniform(
+                RENDER_MODE_INTERPOLATED,
+                gamma_correction,
+            )),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let mut layouts = LayoutCache::default();
+        let camera_bind_group_layout = layouts.get(
+            &device,
+            "camera_bind_group_layout",
+            &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        );
+        // frames in flight each get their own camera and object uniforms,
+        // as many as the highest latency `set_max_frame_latency` accept
+        let frames = FrameRing::new(
+            &device,
+            MAX_FRAME_LATENCY as usize,
+            &camera_bind_group_layout,
+            &model_bind_group_layout,
+            &render_mode_buffer,
+            bytemuck::cast_slice(&camera.view_proj()),
+        );
+        #[cfg(debug_assertions)]
+        info!("Per-frame resource sets: {}", frames.len());
+
+        // directional light uniform
+        let light = LightUniform::new();
+        let light_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("light uniform"),
+            contents: bytemuck::bytes_of(&light),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let light_bind_group_layout = layouts.get(
+            &device,
+            "light_bind_group_layout",
+            &light::LIGHT_LAYOUT_ENTRIES,
+        );
+        let light_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("light bind group"),
+            layout: &light_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: light_buffer.as_entire_binding(),
+            }],
+        });
+
+        let push_constant_ranges = if push_constants {
+            vec![wgpu::PushConstantRange {
+                stages: wgpu::ShaderStages::FRAGMENT,
+                range: 0..pipeline::PUSH_CONSTANT_SIZE,
+            }]
+        } else {
+            Vec::new()
+        };
         let render_pipeline_layout =
             device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                 label: Some("pipe_line_layout"),
-                bind_group_layouts: &[],
-                push_constant_ranges: &[],
+                bind_group_layouts: &[
+                    &model_bind_group_layout,
+                    &camera_bind_group_layout,
+                    &light_bind_group_layout,
+                ],
+                push_constant_ranges: &push_constant_ranges,
             });
 
-        let color_target = [Some(wgpu::ColorTargetState {
-            format: surface_format.clone(),
-            blend: Some(BlendState::REPLACE),
-            write_mask: ColorWrites::ALL,
-        })];
-        let pipeline_desc = RenderPipelineDescriptor {
-            label: Some("render_pipeline"),
-            layout: Some(&render_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: Some("vs_main"),
-                compilation_options: PipelineCompilationOptions::default(),
-                buffers: &[Vertex::desc()],
+        let depth_stencil = pipeline::scene_depth_stencil(options.stencil.clone());
+        let primitive = pipeline::scene_primitive(options.front_face, options.cull_mode);
+        #[cfg(debug_assertions)]
+        info!(
+            "Front face {:?}, cull mode {:?}",
+            primitive.front_face, primitive.cull_mode
+        );
+        let render_pipeline = pipeline::create_pipeline(
+            &device,
+            &render_pipeline_layout,
+            &shader,
+            pipeline::color_target(surface_format, BlendState::REPLACE),
+            1,
+            primitive,
+            depth_stencil.clone(),
+        );
+        let alpha_pipeline = pipeline::create_pipeline(
+            &device,
+            &render_pipeline_layout,
+            &shader,
+            pipeline::color_target(surface_format, BlendState::ALPHA_BLENDING),
+            1,
+            primitive,
+            depth_stencil.clone(),
+        );
+        // write reference value to stencil, leave color untouched
+        let mask_face = wgpu::StencilFaceState {
+            compare: wgpu::CompareFunction::Always,
+            fail_op: wgpu::StencilOperation::Keep,
+            depth_fail_op: wgpu::StencilOperation::Keep,
+            pass_op: wgpu::StencilOperation::Replace,
+        };
+        let mask_pipeline = pipeline::create_pipeline(
+            &device,
+            &render_pipeline_layout,
+            &shader,
+            wgpu::ColorTargetState {
+                write_mask: wgpu::ColorWrites::empty(),
+                ..pipeline::color_target(surface_format, BlendState::REPLACE)
             },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: Some("fs_main"),
-                compilation_options: PipelineCompilationOptions::default(),
-                targets: &color_target,
+            1,
+            primitive,
+            pipeline::scene_depth_stencil(wgpu::StencilState {
+                front: mask_face,
+                back: mask_face,
+                read_mask: 0xff,
+                write_mask: 0xff,
             }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
-                unclipped_depth: false,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                conservative: false,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
+        );
+        let wireframe_pipeline = device
+            .features()
+            .contains(Features::POLYGON_MODE_LINE)
+            .then(|| {
+                pipeline::create_pipeline(
+                    &device,
+                    &render_pipeline_layout,
+                    &shader,
+                    pipeline::color_target(surface_format, BlendState::REPLACE),
+                    1,
+                    wgpu::PrimitiveState {
+                        polygon_mode: PolygonMode::Line,
+                        ..primitive
+                    },
+                    depth_stencil.clone(),
+                )
+            });
 
-            multiview: None,
-            cache: None,
-        };
+        let depth_prepass = options.depth_prepass.then(|| DepthPrepass {
+            mask: pipeline::create_depth_pipeline(
+                &device,
+                &render_pipeline_layout,
+                &shader,
+                primitive,
+                pipeline::scene_depth_stencil(wgpu::StencilState {
+                    front: mask_face,
+                    back: mask_face,
+                    read_mask: 0xff,
+                    write_mask: 0xff,
+                }),
+            ),
+            depth: pipeline::create_depth_pipeline(
+                &device,
+                &render_pipeline_layout,
+                &shader,
+                primitive,
+                pipeline::prepass_depth_stencil(options.stencil.clone()),
+            ),
+            shade: pipeline::create_pipeline(
+                &device,
+                &render_pipeline_layout,
+                &shader,
+                pipeline::color_target(surface_format, BlendState::REPLACE),
+                1,
+                primitive,
+                pipeline::equal_depth_stencil(),
+            ),
+        });
+
+        // default to a single instance with no transform
+        let instance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("instance buffer"),
+            contents: bytemuck::cast_slice(&[InstanceRaw {
+                model: IDENTITY_MATRIX,
+            }]),
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let compute = options.compute.then(|| ComputeAnimator::new(&device));
+        let post_process = (options.post_process || options.fxaa).then(|| {
+            let layout = layouts.get(
+                &device,
+                "texture_bind_group_layout",
+                &texture::TEXTURE_LAYOUT_ENTRIES,
+            );
+            PostProcess::new(&device, layout, w, h, surface_format, options.fxaa)
+        });
+        let blitter = Blitter::new(
+            &device,
+            layouts.get(
+                &device,
+                "texture_bind_group_layout",
+                &texture::TEXTURE_LAYOUT_ENTRIES,
+            ),
+        );
+        // overlay is drawn with the text renderer
+        let text = (options.text || options.debug_overlay)
+            .then(|| TextRenderer::new(&device, &queue, surface_format));
+
+        let gpu_timer = device
+            .features()
+            .contains(Features::TIMESTAMP_QUERY)
+            .then(|| GpuTimer::new(&device, &queue, 1 + options.depth_prepass as u32));
 
-        let render_pipeline = device.create_render_pipeline(&pipeline_desc);
+        let texture_layout = layouts.get(
+            &device,
+            "texture_bind_group_layout",
+            &texture::TEXTURE_LAYOUT_ENTRIES,
+        );
+        let mut samplers = SamplerCache::new(options.max_anisotropy);
+        // drawn in place of textures still loading
+        let placeholder = Texture::from_rgba(
+            &device,
+            &queue,
+            &texture_layout,
+            samplers.get(&device, SamplerOptions::default()),
+            2,
+            2,
+            &PLACEHOLDER_PIXELS,
+            false,
+        );
+        let sprites = SpriteBatch::new(
+            &device,
+            &camera_bind_group_layout,
+            &texture_layout,
+            surface_format,
+            pipeline::scene_depth_stencil(wgpu::StencilState::default()),
+        );
+        let particles = ParticleSystem::new(
+            &device,
+            &camera_bind_group_layout,
+            surface_format,
+            pipeline::scene_depth_stencil(wgpu::StencilState::default()),
+        );
+        let debug_lines = DebugLines::new(
+            &device,
+            &camera_bind_group_layout,
+            surface_format,
+            pipeline::scene_depth_stencil(wgpu::StencilState::default()),
+        );
+        let depth_stencil_view = create_depth_stencil_view(&device, w, h);
 
-        //create vertext buffer
-        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("vertice triangle"),
-            contents: bytemuck::cast_slice(VERTICES),
-            usage: wgpu::BufferUsages::VERTEX,
+        if let Some(e) = pollster::block_on(device.pop_error_scope()) {
+            return Err(format!("Validation failed creating XApp resources: {}", e));
+        }
+
+        // set by wgpu on driver reset or GPU hang, checked every frame
+        let device_lost = Arc::new(Mutex::new(None));
+        let lost = device_lost.clone();
+        device.set_device_lost_callback(move |reason, message| {
+            // also called when the device is dropped
+            if matches!(
+                reason,
+                wgpu::DeviceLostReason::Unknown | wgpu::DeviceLostReason::Destroyed
+            ) {
+                if let Ok(mut lost) = lost.lock() {
+                    *lost = Some(format!("{:?}: {}", reason, message));
+                }
+            }
         });
 
-        Ok(XApp {
+        let controllers = Controllers::new(sdl_ctx.as_ref());
+        let mut app = XApp {
+            device_lost,
+            builder: None,
+            controllers,
             sdl_ctx: sdl_ctx,
             #[cfg(target_os = "android")]
             wgpu_intance: instance,
-            surface: surface,
+            target,
             device: device,
             config: config,
             surface_format: surface_format,
+            surface_capabilities: SurfaceCapabilitiesSummary::default(),
+            surface_source: None,
+            windows: Vec::new(),
+            remember_window_state: false,
             queue: queue,
+            adapter,
+            discrete_adapter: None,
             pipeline: render_pipeline,
+            alpha_pipeline,
+            mask_pipeline,
+            wireframe_pipeline,
+            depth_prepass,
+            topology_pipelines: HashMap::new(),
+            scene_shader: shader,
+            scene_pipeline_layout: render_pipeline_layout,
+            scene_depth_stencil: depth_stencil,
+            scene_primitive: primitive,
+            wireframe: false,
+            draw_geometry: options.draw_geometry,
+            scene_pass: ScenePass::default(),
+            passes: RefCell::new(PassList::new()),
+            render_scale: 1.0,
+            depth_stencil_view,
+            stencil_reference: 0,
+            push_constants,
+            compute,
+            post_process,
+            blitter,
+            text,
+            gpu_timer,
             window: window,
-            window_height: w,
-            window_width: h,
+            window_height: h,
+            window_width: w,
             // event_pump: event_pump,
-            vertex_buffer: vertex_buffer,
-        })
-    }
+            frames,
+            model_bind_group_layout,
+            objects: Vec::new(),
+            instance_buffer,
+            instance_count: 1,
+            pending_instances: Cell::new(None),
+            single_instance: Some(IDENTITY_MATRIX),
+            frustum_culling: options.frustum_culling,
+            culled: Cell::new(0),
+            camera,
+            orbit_camera: None,
+            render_mode: RENDER_MODE_INTERPOLATED,
+            render_mode_buffer,
+            gamma_correction,
+            light,
+            light_buffer,
+            light_dirty: Cell::new(false),
+            staging_belt: options
+                .staging_belt
+                .then(|| RefCell::new(StagingBelt::new(STAGING_CHUNK_SIZE))),
+            light_bind_group,
+            input: InputState::new(),
+            texture_layout,
+            layouts,
+            samplers,
+            requested_samplers: HashMap::new(),
+            placeholder_texture: placeholder.as_ref().ok().map(|_| 0),
+            textures: placeholder.into_iter().collect(),
+            sprites,
+            particles,
+            debug_lines,
+            skybox: None,
+            loader: ResourceLoader::new(),
+            loaded_textures: HashMap::new(),
+            loaded_meshes: HashMap::new(),
+            title: String::new(),
+            fps_counter: FpsCounter::new(),
+            clock: Box::new(SystemClock::new()),
+            show_fps_in_title: false,
+            debug_overlay: options.debug_overlay,
+            show_overlay: false,
+            frame_time: 0.0,
+            fixed_timestep: valid_timestep(options.fixed_timestep),
+            target_fps: options.target_fps,
+            on_demand: options.on_demand,
+            check_winding: options.check_winding,
+            needs_redraw: true,
+            focused: true,
+            minimized: false,
+            paused: false,
+            reset_frame_timer: false,
+            pending_resize: None,
+        };
+        if let Some(window) = &app.window {
+            app.title = window.title().to_string();
+        }
+        app.input.set_pixel_scale(app.pixel_scale());
 
-    pub fn run(&self) -> Result<(), String> {
-        let mut event_pump = self.sdl_ctx.event_pump()?;
+        app.set_wireframe(options.wireframe);
 
-        'run: loop {
-            for event in event_pump.poll_iter() {
-                match event {
-                    Event::Quit { timestamp } => {
-                        #[cfg(debug_assertions)]
-                        {
-                            info!("Exiting XApp. Running for about {}", timestamp);
-                        }
-                        break 'run;
-                    }
-                    Event::KeyDown {
-                        keycode: Some(Keycode::Escape),
-                        timestamp,
-                        ..
-                    } => {
-                        #[cfg(all(not(target_os = "android"), debug_assertions))]
-                        {
-                            info!(
-                                "Exiting XApp from escape key. Running for about {}",
-                                timestamp
-                            );
-                            break 'run;
-                        }
-                    }
+        // default scene
+        app.add_object(VERTICES, INDICES);
 
-                    Event::AppWillEnterForeground { timestamp } => {
-                        #[cfg(debug_assertions)]
-                        {
-                            info!(
-                                "Will enter foreground (onResume) XApp. Running for about {}",
-                                timestamp
-                            );
-                        }
+        Ok(app)
+    }
 
-                        #[cfg(target_os = "android")]
-                        {
-                            let inst = self.wgpu_intance;
-                            let inst = match inst.as_ref() {
-                                Some(x) => x,
-                                None => {
-                                    return Err("WGPU intance is empty".to_string());
-                                }
-                            };
-                            let _ = self.init_surface(inst)?;
-                        }
-                    }
-                    e => {
-                        #[cfg(debug_assertions)]
-                        info!("{:?}", e);
-                    }
+    /// Upload a mesh to the GPU and add it to the scene.
+    /// Return index of the object that can be used to access it later.
+    pub fn add_object(&mut self, vertices: &[Vertex], indices: &[u16]) -> usize {
+        self.add_object_with(vertices, Indices::U16(indices))
+    }
+
+    /// Same as `add_object` with 32 bit indices, for meshes with more than
+    /// 65536 vertices.
+    pub fn add_object_u32(&mut self, vertices: &[Vertex], indices: &[u32]) -> usize {
+        self.add_object_with(vertices, Indices::U32(indices))
+    }
+
+    /// Add a filled convex polygon of `color` on the `z = 0` plane, its
+    /// corners `points` in order around it, either way. It is split in a
+    /// fan of triangles from the first point, facing the default camera
+    /// whatever the builder front face is. Concave polygons overlap
+    /// themselves. Fail with less than 3 points.
+    pub fn add_polygon(&mut self, points: &[[f32; 2]], color: [f32; 3]) -> Result<usize, String> {
+        if points.len() < 3 {
+            return Err(format!(
+                "Polygon need at least 3 points, got {}",
+                points.len()
+            ));
+        }
+        let vertices: Vec<Vertex> = points
+            .iter()
+            .map(|p| Vertex {
+                position: [p[0], p[1], 0.0],
+                color,
+                normal: [0.0, 0.0, -1.0],
+            })
+            .collect();
+        // shoelace formula, positive when counter clockwise with y up
+        let area: f32 = points
+            .iter()
+            .zip(points.iter().cycle().skip(1))
+            .map(|(a, b)| a[0] * b[1] - b[0] * a[1])
+            .sum();
+        let keep_order = (area > 0.0) == (self.scene_primitive.front_face == wgpu::FrontFace::Ccw);
+        let indices: Vec<u32> = (1..points.len() as u32 - 1)
+            .flat_map(|i| {
+                if keep_order {
+                    [0, i, i + 1]
+                } else {
+                    [0, i + 1, i]
                 }
-            }
+            })
+            .collect();
+        Ok(self.add_mesh(&vertices, &indices))
+    }
 
-            self.render()?;
+    /// Add mesh with 16 bit indices when they are enough, 32 bit otherwise.
+    fn add_mesh(&mut self, vertices: &[Vertex], indices: &[u32]) -> usize {
+        if vertices.len() <= u16::MAX as usize + 1 {
+            let indices: Vec<u16> = indices.iter().map(|&i| i as u16).collect();
+            self.add_object(vertices, &indices)
+        } else {
+            self.add_object_u32(vertices, indices)
         }
-        Ok(())
     }
 
-    fn render(&self) -> Result<(), String> {
-        let output = self
-            .surface
-            .get_current_texture()
-            .map_err(|e| e.to_string())?;
+    fn add_object_with(&mut self, vertices: &[Vertex], indices: Indices) -> usize {
+        let vertex_usage = if self.compute.is_some() {
+            wgpu::BufferUsages::STORAGE
+        } else {
+            wgpu::BufferUsages::empty()
+        };
+        self.frames.reserve(
+            &self.device,
+            &self.model_bind_group_layout,
+            self.objects.len() + 1,
+        );
+        let mut object = RenderObject::new(&self.device, vertices, indices, vertex_usage);
+        if let Some(compute) = &self.compute {
+            object.compute =
+                Some(compute.bind_object(&self.device, &object.vertex_buffer, vertices));
+        }
+        self.objects.push(object);
+        self.objects.len() - 1
+    }
 
-        let view = output
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+    /// Parse Wavefront OBJ `bytes` and add it to the scene as one object,
+    /// see `obj` module for supported subset. Vertex use the `Vertex`
+    /// layout: position at location 0, color at location 1, white unless
+    /// the `v x y z r g b` extension is used, and normal at location 2,
+    /// from `vn` lines or `compute_normals`. `z` is negated so the right
+    /// handed model face the camera.
+    pub fn load_obj(&mut self, bytes: &[u8]) -> Result<usize, String> {
+        let (vertices, indices) = obj::parse_obj(bytes)?;
+        Ok(self.add_loaded_mesh(&vertices, &indices))
+    }
 
-        let mut encoder = self
-            .device
-            .create_command_encoder(&CommandEncoderDescriptor {
-                label: Some("Render encoder"),
-            });
+    /// `add_mesh` after warning on debug build when most triangles would
+    /// be culled.
+    fn add_loaded_mesh(&mut self, vertices: &[Vertex], indices: &[u32]) -> usize {
+        if cfg!(debug_assertions) && self.check_winding {
+            self.check_winding(vertices, indices);
+        }
+        self.add_mesh(vertices, indices)
+    }
 
-        {
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.1,
-                            g: 0.2,
-                            b: 0.3,
-                            a: 1.0,
-                        }),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
-                occlusion_query_set: None,
-                timestamp_writes: None,
-            });
+    /// Count the triangles of `vertices` and `indices`, a triangle list,
+    /// facing the current camera or away from it, before any model
+    /// matrix. Warn with a fix when culled ones clearly outnumber the
+    /// drawn ones with the builder front face and cull mode, the usual
+    /// reason of invisible geometry. Closed meshes never warn, half of
+    /// them face away anyway.
+    pub fn check_winding(&self, vertices: &[Vertex], indices: &[u32]) -> WindingReport {
+        let report = validate::check_winding(
+            vertices,
+            indices,
+            &self.view_proj(&self.camera),
+            self.scene_primitive.front_face,
+            self.scene_primitive.cull_mode,
+        );
+        if report.is_mostly_culled() {
+            let flipped = match self.scene_primitive.front_face {
+                wgpu::FrontFace::Ccw => wgpu::FrontFace::Cw,
+                wgpu::FrontFace::Cw => wgpu::FrontFace::Ccw,
+            };
+            warn!(
+                "{} of {} triangles are culled with front face {:?} and cull mode {:?}, \
+                 reverse the index order of each triangle or use \
+                 XAppBuilder::front_face({:?})",
+                report.culled,
+                report.front_facing + report.back_facing,
+                self.scene_primitive.front_face,
+                self.scene_primitive.cull_mode,
+                flipped
+            );
+        }
+        if report.out_of_range > 0 {
+            warn!(
+                "{} triangles use an index past the last of {} vertices",
+                report.out_of_range,
+                vertices.len()
+            );
+        }
+        report
+    }
 
-            render_pass.set_pipeline(&self.pipeline);
-            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-            render_pass.draw(0..3, 0..1);
-            render_pass.draw(0..VERTICES.len() as u32, 0..1)
+    /// Draw a cube of six BMP images behind the scene, in face order +X,
+    /// -X, +Y, -Y, +Z, -Z. Each pixel shows the face seen in its view
+    /// direction, whatever the camera position. Faces must be square and
+    /// of the same size. Replace the previous skybox.
+    pub fn set_skybox(&mut self, faces: [&[u8]; 6]) -> Result<(), String> {
+        let mut size = None;
+        let mut pixels = Vec::new();
+        for (i, face) in faces.iter().enumerate() {
+            let (width, height, rgba) = loader::decode_bmp(face)?;
+            if width != height || size.is_some_and(|x| x != width) {
+                return Err(format!(
+                    "Skybox face {} is {}x{}, faces must be square and of the same size",
+                    i, width, height
+                ));
+            }
+            size = Some(width);
+            pixels.extend_from_slice(&rgba);
         }
+        self.skybox = Some(Skybox::new(
+            &self.device,
+            &self.queue,
+            self.surface_format,
+            size.unwrap_or(1),
+            &pixels,
+        ));
+        Ok(())
+    }
 
-        self.queue.submit([encoder.finish()]);
-        output.present();
+    /// Remove the skybox, the scene pass clear color shows again.
+    pub fn clear_skybox(&mut self) {
+        self.skybox = None;
+    }
 
-        Ok(())
+    /// Queue `text` for the next frame, top left corner at pixel `x`, `y`.
+    /// It is drawn after everything else. Does nothing unless enabled with
+    /// `XAppBuilder::text`.
+    pub fn draw_text(&mut self, text: &str, x: f32, y: f32) {
+        if let Some(renderer) = &mut self.text {
+            renderer.queue_text(text, x, y, [1.0; 4]);
+        }
+    }
+
+    /// Duration in millisecond of the scene render pass on the GPU, from a
+    /// recent frame. `None` when the device doesn't support
+    /// `TIMESTAMP_QUERY` or before the first result is read back.
+    /// With `XAppBuilder::depth_prepass`, sum of the depth prepass and the
+    /// scene pass.
+    pub fn last_gpu_frame_ms(&self) -> Option<f32> {
+        self.gpu_timer.as_ref().and_then(|timer| timer.last_ms())
+    }
+
+    /// Every adapter of `backends` wgpu can see, in the order used by
+    /// `XAppBuilder::adapter_index`.
+    pub fn enumerate_adapters(backends: Backends) -> Vec<AdapterSummary> {
+        let instance = Instance::new(wgpu::InstanceDescriptor {
+            backends,
+            ..Default::default()
+        });
+        instance
+            .enumerate_adapters(backends)
+            .iter()
+            .map(|x| AdapterSummary::from(&x.get_info()))
+            .collect()
+    }
+
+    /// Backend, name and type of the GPU used for rendering.
+    pub fn adapter_info(&self) -> &AdapterSummary {
+        &self.adapter
+    }
+
+    /// One line describing the GPU and output, written to the SDL log at
+    /// startup whatever the log level, to paste in bug reports.
+    pub fn init_summary(&self) -> String {
+        format!(
+            "XApp {}: backend {:?}, adapter {} ({:?}), format {:?}, view format {:?}, \
+             present mode {:?}, size {}x{}, {}",
+            env!("CARGO_PKG_VERSION"),
+            self.adapter.backend,
+            self.adapter.name,
+            self.adapter.device_type,
+            self.config.format,
+            self.surface_format,
+            self.config.present_mode,
+            self.config.width,
+            self.config.height,
+            if self.window.is_some() {
+                "windowed"
+            } else {
+                "headless"
+            }
+        )
+    }
+
+    /// Discrete GPU of the current backend when the app is running on
+    /// another kind of adapter, e.g. the integrated GPU the OS picked on a
+    /// laptop. Looked up once at init, which warn recommending it. `None`
+    /// when the app already use a discrete GPU, there is none, or on
+    /// headless app.
+    pub fn discrete_adapter(&self) -> Option<AdapterSummary> {
+        self.discrete_adapter.clone()
+    }
+
+    /// Rebuild the app on the discrete GPU found by `discrete_adapter`,
+    /// with a new window, device and default scene like after a device
+    /// lost, so objects, textures and other GPU resources must be created
+    /// again. Return whether the adapter changed. Fail on headless app.
+    pub fn switch_to_discrete_adapter(&mut self) -> Result<bool, String> {
+        let Some(discrete) = self.discrete_adapter() else {
+            return Ok(false);
+        };
+        let mut builder = self
+            .builder
+            .clone()
+            .ok_or("Headless XApp can't switch adapter")?;
+        builder.adapter = Some(AdapterSelection::Name(discrete.name));
+        self.recreate_with(&builder)?;
+        #[cfg(debug_assertions)]
+        info!("Switched to adapter {}", self.adapter);
+        Ok(true)
+    }
+
+    /// Upload `width` x `height` sRGB RGBA8 pixels as texture usable by
+    /// `draw_sprite`, sampled linear and clamped, with mipmaps. Return the
+    /// texture id.
+    pub fn create_texture(
+        &mut self,
+        width: u32,
+        height: u32,
+        rgba: &[u8],
+    ) -> Result<usize, String> {
+        self.create_texture_with_sampler(width, height, rgba, SamplerOptions::default())
+    }
+
+    /// Same as `create_texture`, sampled with `sampler`.
+    pub fn create_texture_with_sampler(
+        &mut self,
+        width: u32,
+        height: u32,
+        rgba: &[u8],
+        sampler: SamplerOptions,
+    ) -> Result<usize, String> {
+        let texture = Texture::from_rgba(
+            &self.device,
+            &self.queue,
+            &self.texture_layout,
+            self.samplers.get(&self.device, sampler),
+            width,
+            height,
+            rgba,
+            sampler.mipmaps,
+        )?;
+        self.textures.push(texture);
+        Ok(self.textures.len() - 1)
+    }
+
+    pub fn texture(&self, id: usize) -> Option<&Texture> {
+        self.textures.get(id)
+    }
+
+    /// Load BMP file at `path` on the loader thread. Use `texture_id` to
+    /// draw it, a placeholder is drawn until it is uploaded.
+    pub fn request_texture(&self, path: impl AsRef<Path>) -> TextureHandle {
+        self.loader.request_texture(path.as_ref().to_path_buf())
+    }
+
+    /// Same as `request_texture`, sampled with `sampler` once uploaded.
+    pub fn request_texture_with_sampler(
+        &mut self,
+        path: impl AsRef<Path>,
+        sampler: SamplerOptions,
+    ) -> TextureHandle {
+        let handle = self.request_texture(path);
+        self.requested_samplers.insert(handle, sampler);
+        handle
+    }
+
+    /// Texture id of `handle` once uploaded, the placeholder texture id
+    /// before that or when loading failed.
+    pub fn texture_id(&self, handle: TextureHandle) -> Option<usize> {
+        self.loaded_textures
+            .get(&handle)
+            .copied()
+            .or(self.placeholder_texture)
+    }
+
+    /// Load OBJ file at `path` on the loader thread, it is added to the
+    /// scene once parsed.
+    pub fn request_mesh(&self, path: impl AsRef<Path>) -> MeshHandle {
+        self.loader.request_mesh(path.as_ref().to_path_buf())
+    }
+
+    /// Object id of `handle`, `None` until it is added to the scene.
+    pub fn mesh_object(&self, handle: MeshHandle) -> Option<usize> {
+        self.loaded_meshes.get(&handle).copied()
+    }
+
+    /// Upload resources decoded by the loader thread. Called every frame by
+    /// `run`, headless apps call it before `render_frame`.
+    pub fn poll_loader(&mut self) {
+        for loaded in self.loader.finished() {
+            self.needs_redraw = true;
+            match loaded {
+                Loaded::Texture(handle, result) => {
+                    let sampler = self.requested_samplers.remove(&handle).unwrap_or_default();
+                    match result.and_then(|(w, h, pixels)| {
+                        self.create_texture_with_sampler(w, h, &pixels, sampler)
+                    }) {
+                        Ok(id) => {
+                            self.loaded_textures.insert(handle, id);
+                        }
+                        Err(e) => warn!("Cannot load texture: {}", e),
+                    }
+                }
+                Loaded::Mesh(handle, result) => match result {
+                    Ok((vertices, indices)) => {
+                        let id = self.add_loaded_mesh(&vertices, &indices);
+                        self.loaded_meshes.insert(handle, id);
+                    }
+                    Err(e) => warn!("Cannot load mesh: {}", e),
+                },
+            }
+        }
+    }
+
+    /// Start collecting sprites. Sprites from the previous `end_sprites`
+    /// keep being drawn every frame until the next `end_sprites`.
+    pub fn begin_sprites(&mut self) {
+        self.sprites.begin();
+    }
+
+    /// Queue textured quad in world space. `dest_rect` is bottom left x, y,
+    /// width and height, `src_rect` is uv top left x, y, width and height.
+    pub fn draw_sprite(
+        &mut self,
+        texture_id: usize,
+        dest_rect: [f32; 4],
+        src_rect: [f32; 4],
+        tint: [f32; 4],
+    ) {
+        self.sprites
+            .draw_sprite(texture_id, dest_rect, src_rect, tint);
+    }
+
+    /// Upload collected sprites, sorted by texture so each texture is one
+    /// instanced draw. Sprites are drawn over the objects.
+    pub fn end_sprites(&mut self) {
+        self.sprites.end(&self.device, &self.queue);
+    }
+
+    /// Particles drawn over the sprites, to change their settings.
+    pub fn particles(&mut self) -> &mut ParticleSystem {
+        &mut self.particles
+    }
+
+    /// Spawn `count` particles at world position `origin`.
+    pub fn emit_particles(&mut self, count: usize, origin: [f32; 2]) {
+        self.particles.emit(count, origin);
+    }
+
+    /// Move particles by `dt` and upload them for drawing, usually called
+    /// from `Game::update`.
+    pub fn update_particles(&mut self, dt: f32) {
+        self.particles.update(dt);
+        self.particles.upload(&self.queue);
+    }
+
+    /// Queue line from world position `a` to `b` for the next frame, drawn
+    /// over sprites and particles with the scene camera.
+    pub fn draw_line(&mut self, a: [f32; 3], b: [f32; 3], color: [f32; 4]) {
+        self.debug_lines.draw_line(&self.device, a, b, color);
+    }
+
+    /// Queue the edges of the axis aligned box from `min` to `max` for the
+    /// next frame, see `draw_line`.
+    pub fn draw_box(&mut self, min: [f32; 3], max: [f32; 3], color: [f32; 4]) {
+        self.debug_lines.draw_box(&self.device, min, max, color);
+    }
+
+    /// Replace instance list used to draw every object.
+    /// Buffer is only reallocated when it is too small to hold `instances`.
+    pub fn set_instances(&mut self, instances: &[InstanceRaw]) {
+        let data: &[u8] = bytemuck::cast_slice(instances);
+        if data.len() as wgpu::BufferAddress > self.instance_buffer.size() {
+            // older instances not uploaded yet are replaced too
+            self.pending_instances.set(None);
+            self.instance_buffer =
+                self.device
+                    .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("instance buffer"),
+                        contents: data,
+                        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                    });
+        } else if self.staging_belt.is_some() {
+            self.pending_instances.set(Some(data.to_vec()));
+        } else {
+            self.queue.write_buffer(&self.instance_buffer, 0, data);
+        }
+        self.instance_count = instances.len() as u32;
+        self.single_instance = match instances {
+            [instance] => Some(instance.model),
+            _ => None,
+        };
+    }
+
+    pub fn object(&self, id: usize) -> Option<&RenderObject> {
+        self.objects.get(id)
+    }
+
+    pub fn object_mut(&mut self, id: usize) -> Option<&mut RenderObject> {
+        self.objects.get_mut(id)
+    }
+
+    /// Objects skipped in the last frame because their bounds were out of
+    /// the camera view, see `XAppBuilder::frustum_culling`.
+    pub fn culled_count(&self) -> u32 {
+        self.culled.get()
+    }
+
+    /// Min and max world corners of object `id`, see
+    /// `RenderObject::bounds`. `None` when there is no such object.
+    pub fn object_bounds(&self, id: usize) -> Option<([f32; 3], [f32; 3])> {
+        self.objects.get(id).map(|x| x.bounds())
+    }
+
+    /// Move the camera so every object fit in the view: the orbit camera
+    /// when set, keeping its direction, otherwise `Camera2D` position and
+    /// zoom. Do nothing without objects.
+    pub fn frame_all(&mut self) {
+        let Some((min, max)) =
+            self.objects
+                .iter()
+                .map(|x| x.bounds())
+                .reduce(|(a_min, a_max), (b_min, b_max)| {
+                    (
+                        std::array::from_fn(|i| a_min[i].min(b_min[i])),
+                        std::array::from_fn(|i| a_max[i].max(b_max[i])),
+                    )
+                })
+        else {
+            return;
+        };
+        let center: [f32; 3] = std::array::from_fn(|i| (min[i] + max[i]) * 0.5);
+        let half: [f32; 3] = std::array::from_fn(|i| (max[i] - min[i]) * 0.5);
+        let aspect = self.camera.aspect();
+        match &mut self.orbit_camera {
+            Some(orbit) => {
+                // distance where the sphere around the box touch the
+                // narrowest side of the view
+                let radius = half.iter().map(|x| x * x).sum::<f32>().sqrt().max(0.01);
+                let half_fov_y = orbit.fov_y * 0.5;
+                let half_fov_x = (half_fov_y.tan() * aspect).atan();
+                orbit.target = center;
+                orbit.distance = radius / half_fov_y.min(half_fov_x).sin();
+            }
+            None => {
+                // at zoom 1.0 the view is 2 units high and 2 * aspect wide
+                self.camera.position = [center[0], center[1]];
+                let zoom = (1.0 / half[1]).min(aspect / half[0]);
+                self.camera.zoom = if zoom.is_finite() { zoom } else { 1.0 };
+                // clamp into the zoom range
+                self.camera.zoom_by(1.0);
+            }
+        }
+    }
+
+    pub fn camera(&self) -> &Camera2D {
+        &self.camera
+    }
+
+    pub fn camera_mut(&mut self) -> &mut Camera2D {
+        &mut self.camera
+    }
+
+    /// Switch the scene camera between orthographic and perspective and
+    /// upload it at once. The aspect ratio follow the window in both.
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.camera.set_projection(projection);
+        self.frames.write_camera(
+            &self.queue,
+            bytemuck::cast_slice(&self.view_proj(&self.camera)),
+        );
+    }
+
+    /// Look at the scene through `camera` instead of `Camera2D`, moved by
+    /// the mouse every frame, see `OrbitCamera::update`. `None` go back to
+    /// `Camera2D`.
+    pub fn set_orbit_camera(&mut self, camera: Option<OrbitCamera>) {
+        self.orbit_camera = camera;
+    }
+
+    pub fn orbit_camera(&self) -> Option<&OrbitCamera> {
+        self.orbit_camera.as_ref()
+    }
+
+    pub fn orbit_camera_mut(&mut self) -> Option<&mut OrbitCamera> {
+        self.orbit_camera.as_mut()
+    }
+
+    /// Content of the camera uniform: the orbit camera when set, otherwise
+    /// `camera`. Both use the aspect ratio of `camera` viewport.
+    fn view_proj(&self, camera: &Camera2D) -> [[f32; 4]; 4] {
+        match &self.orbit_camera {
+            Some(orbit) => orbit.view_proj(camera.aspect()),
+            None => camera.view_proj(),
+        }
+    }
+
+    pub fn input(&self) -> &InputState {
+        &self.input
+    }
+
+    /// Pixels per window point, above 1.0 on HiDPI displays. Surface and
+    /// `InputState` positions are in pixels, except
+    /// `mouse_position_logical`, SDL window size in points.
+    /// Always 1.0 for headless app.
+    pub fn pixel_scale(&self) -> f32 {
+        match &self.window {
+            Some(window) => {
+                let (points, _) = window.size();
+                let (pixels, _) = drawable_size(window);
+                if points == 0 {
+                    1.0
+                } else {
+                    pixels as f32 / points as f32
+                }
+            }
+            None => 1.0,
+        }
+    }
+
+    /// Reconfigure surface and camera viewport for new window size.
+    fn resize(&mut self, width: u32, height: u32) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        self.window_width = width;
+        self.window_height = height;
+        self.config.width = width;
+        self.config.height = height;
+        match &mut self.target {
+            RenderTarget::Surface(surface) => surface.configure(&self.device, &self.config),
+            RenderTarget::Offscreen(texture) => {
+                *texture = create_offscreen_texture(&self.device, &self.config)
+            }
+            RenderTarget::Released => {}
+        }
+        self.resize_scene_targets();
+        self.camera.set_viewport(width, height);
+        self.input.set_pixel_scale(self.pixel_scale());
+    }
+
+    /// Remember the size of a `SizeChanged` event, the surface is resized
+    /// once per frame by `apply_pending_resize` whatever the number of
+    /// events.
+    fn queue_resize(&mut self, event: &Event) {
+        self.pending_resize = coalesce_resize(
+            self.pending_resize,
+            event,
+            self.window.as_ref().map(drawable_size),
+        );
+    }
+
+    /// Resize to the size queued since the last call, return `true` when
+    /// the surface was reconfigured.
+    fn apply_pending_resize(&mut self) -> bool {
+        match self.pending_resize.take() {
+            Some((width, height)) => {
+                self.resize(width, height);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Query the drawable size again and resize the surface and the size
+    /// dependent textures when it changed, e.g. after the window moved to
+    /// a display of another pixel density. Called by `run` on
+    /// `DisplayChanged` and `ICCProfChanged` events. Do nothing on
+    /// headless app.
+    pub fn refresh_display(&mut self) {
+        let Some(window) = &self.window else {
+            return;
+        };
+        let size = drawable_size(window);
+        if size == (self.config.width, self.config.height) {
+            return;
+        }
+        #[cfg(debug_assertions)]
+        {
+            info!(
+                "Display changed, drawable size {:?} -> {:?}, pixel scale {}",
+                (self.config.width, self.config.height),
+                size,
+                self.pixel_scale()
+            );
+        }
+        self.resize(size.0, size.1);
+    }
+
+    /// Size the scene is drawn at, window size scaled by render scale when
+    /// it goes through the post process pass.
+    fn scene_size(&self) -> (u32, u32) {
+        if self.post_process.is_none() {
+            return (self.config.width, self.config.height);
+        }
+        let scale = |x: u32| ((x as f32 * self.render_scale).round() as u32).max(1);
+        (scale(self.config.width), scale(self.config.height))
+    }
+
+    fn resize_scene_targets(&mut self) {
+        let (width, height) = self.scene_size();
+        self.depth_stencil_view = create_depth_stencil_view(&self.device, width, height);
+        if let Some(post) = &mut self.post_process {
+            post.resize(&self.device, width, height);
+        }
+    }
+
+    /// Draw the scene at `scale` times the window resolution, clamped to
+    /// `0.25..=2.0`, then stretch it to the window in the post process
+    /// pass. Lower scale trade sharpness for speed on slow devices. Enable
+    /// post process when it isn't already.
+    pub fn set_render_scale(&mut self, scale: f32) {
+        self.render_scale = if scale.is_finite() {
+            scale.clamp(MIN_RENDER_SCALE, MAX_RENDER_SCALE)
+        } else {
+            1.0
+        };
+        if self.post_process.is_none() {
+            let layout = self.layouts.get(
+                &self.device,
+                "texture_bind_group_layout",
+                &texture::TEXTURE_LAYOUT_ENTRIES,
+            );
+            self.post_process = Some(PostProcess::new(
+                &self.device,
+                layout,
+                self.config.width,
+                self.config.height,
+                self.surface_format,
+                false,
+            ));
+        }
+        self.resize_scene_targets();
+    }
+
+    pub fn render_scale(&self) -> f32 {
+        self.render_scale
+    }
+
+    /// Create color texture with the window size, usable as render target
+    /// of custom passes and sampled afterward.
+    pub fn create_offscreen_target(&self, format: TextureFormat) -> OffscreenTarget {
+        OffscreenTarget::new(&self.device, self.config.width, self.config.height, format)
+    }
+
+    /// Record a pass copying `src` over the whole of `dst`, stretched with
+    /// linear filtering when their size differ. `dst` must be of
+    /// `surface_format`, like the window or an offscreen target created
+    /// with it, see `blit_to_format` for others.
+    pub fn blit(&self, encoder: &mut wgpu::CommandEncoder, src: &TextureView, dst: &TextureView) {
+        self.blit_to_format(encoder, src, dst, self.surface_format);
+    }
+
+    /// `blit` into `dst` of `dst_format`. The pipeline of each format is
+    /// created on first use and kept.
+    pub fn blit_to_format(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        src: &TextureView,
+        dst: &TextureView,
+        dst_format: TextureFormat,
+    ) {
+        self.blitter
+            .blit(&self.device, encoder, src, dst, dst_format);
+    }
+
+    /// Create cube texture of `size` x `size` faces with the surface
+    /// format, filled by `render_cubemap`.
+    pub fn create_cube_target(&self, size: u32) -> CubeTarget {
+        let size = size.max(1);
+        CubeTarget::new(
+            &self.device,
+            size,
+            self.surface_format,
+            create_depth_stencil_view(&self.device, size, size),
+        )
+    }
+
+    /// Render the scene into the six faces of `target` as seen from `eye`,
+    /// each face with a 90 degree perspective looking along its axis. The
+    /// scene camera is written back for the next frame.
+    pub fn render_cubemap(&self, target: &CubeTarget, eye: [f32; 3]) {
+        self.frames
+            .current()
+            .object_uniforms
+            .write(&self.queue, &self.objects);
+        // camera buffer is shared, so each face is its own submission
+        for (face, face_view) in target.face_views().iter().enumerate() {
+            self.queue.write_buffer(
+                &self.frames.current().camera_buffer,
+                0,
+                bytemuck::cast_slice(&cubemap::face_view_proj(eye, face)),
+            );
+            let commands = self.encode("Cubemap encoder", |encoder| {
+                self.upload_pending(encoder);
+                self.record_scene_pass(
+                    encoder,
+                    face_view,
+                    target.depth_stencil_view(),
+                    &cubemap::face_view_proj(eye, face),
+                    None,
+                );
+            });
+            self.submit([commands]);
+        }
+        self.queue.write_buffer(
+            &self.frames.current().camera_buffer,
+            0,
+            bytemuck::cast_slice(&self.view_proj(&self.camera)),
+        );
+    }
+
+    /// Mouse wheel zoom and middle button drag pan, or the orbit camera
+    /// controls when it is set.
+    fn update_camera_from_input(&mut self, dt: f32) {
+        if let Some(orbit) = &mut self.orbit_camera {
+            orbit.update(&self.input, dt);
+            return;
+        }
+
+        let wheel = self.input.wheel();
+        if wheel != 0.0 {
+            self.camera.zoom_by(1.1_f32.powf(wheel));
+        }
+
+        if self.input.is_mouse_button_down(MouseButton::Middle) {
+            let (dx, dy) = self.input.mouse_delta();
+            self.camera.pan_by_pixels(dx as f32, dy as f32);
+        }
+    }
+
+    /// Change window title. Does nothing for headless app and on Android,
+    /// where the title is never shown.
+    pub fn set_title(&mut self, title: &str) -> Result<(), String> {
+        self.title = title.to_string();
+        self.apply_title()
+    }
+
+    /// Append live frame rate to the window title, updated every second.
+    pub fn set_show_fps_in_title(&mut self, show: bool) -> Result<(), String> {
+        self.show_fps_in_title = show;
+        self.apply_title()
+    }
+
+    /// Frame rate measured over the last second.
+    pub fn fps(&self) -> f32 {
+        self.fps_counter.fps()
+    }
+
+    /// Set directional light, `direction` is where the light travel to and
+    /// doesn't need to be normalized. Surfaces are lit with Lambert
+    /// diffuse, the default light travel into the screen, toward `+z`,
+    /// with white color.
+    pub fn set_light(&mut self, direction: [f32; 3], color: [f32; 3]) {
+        self.light.set_light(direction, color);
+        self.write_light();
+    }
+
+    /// Light added to every surface whatever its normal, default to 0.
+    pub fn set_ambient_light(&mut self, ambient: f32) {
+        self.light.set_ambient(ambient);
+        self.write_light();
+    }
+
+    fn write_light(&self) {
+        if self.staging_belt.is_some() {
+            self.light_dirty.set(true);
+            return;
+        }
+        self.queue
+            .write_buffer(&self.light_buffer, 0, bytemuck::bytes_of(&self.light));
+    }
+
+    /// Select how vertex colors are shaded, `RENDER_MODE_INTERPOLATED` or
+    /// `RENDER_MODE_FLAT`. Unknown modes are shaded as interpolated.
+    pub fn set_render_mode(&mut self, mode: u32) {
+        self.render_mode = mode;
+        self.queue.write_buffer(
+            &self.render_mode_buffer,
+            0,
+            bytemuck::cast_slice(&render_mode_uniform(mode, self.gamma_correction)),
+        );
+    }
+
+    pub fn render_mode(&self) -> u32 {
+        self.render_mode
+    }
+
+    /// Draw objects as lines. Stay on fill mode, with a warning, when the
+    /// device doesn't support `POLYGON_MODE_LINE`.
+    pub fn set_wireframe(&mut self, enable: bool) {
+        if enable && self.wireframe_pipeline.is_none() {
+            warn!("Wireframe is not supported by the device, keep fill mode");
+            return;
+        }
+        self.wireframe = enable;
+    }
+
+    pub fn is_wireframe(&self) -> bool {
+        self.wireframe
+    }
+
+    /// Stop calling `Game::update` while `Game::render` and events keep
+    /// running, so the window stay responsive. The time spent paused is
+    /// not given to the next update. Toggled with `P` key on debug build.
+    pub fn set_paused(&mut self, paused: bool) {
+        if self.paused && !paused {
+            self.reset_frame_timer = true;
+        }
+        self.paused = paused;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Change color of vertex `index` of object `id`. Only the color bytes
+    /// of that vertex are written, position and normal are left as is.
+    pub fn set_vertex_color(
+        &mut self,
+        id: usize,
+        index: usize,
+        color: [f32; 3],
+    ) -> Result<(), String> {
+        let object = self
+            .objects
+            .get(id)
+            .ok_or_else(|| format!("Object {} doesn't exist", id))?;
+        if index >= object.vertex_count() {
+            return Err(format!(
+                "Vertex {} doesn't exist, object {} has {} vertices",
+                index,
+                id,
+                object.vertex_count()
+            ));
+        }
+        let offset = index * std::mem::size_of::<Vertex>() + std::mem::offset_of!(Vertex, color);
+        self.queue.write_buffer(
+            &object.vertex_buffer,
+            offset as wgpu::BufferAddress,
+            bytemuck::cast_slice(&color),
+        );
+        Ok(())
+    }
+
+    /// Draw object `id` as `topology`, e.g. `LineList` to draw pairs of
+    /// indices as lines. Pipelines of a topology are created the first
+    /// time it is used. Stencil masks and wireframe stay triangles.
+    pub fn set_topology(&mut self, id: usize, topology: PrimitiveTopology) -> Result<(), String> {
+        let object = self
+            .objects
+            .get_mut(id)
+            .ok_or_else(|| format!("Object {} doesn't exist", id))?;
+        object.topology = topology;
+        if topology == PrimitiveTopology::TriangleList
+            || self.topology_pipelines.contains_key(&topology)
+        {
+            return Ok(());
+        }
+        let create = |blend| {
+            pipeline::create_pipeline(
+                &self.device,
+                &self.scene_pipeline_layout,
+                &self.scene_shader,
+                pipeline::color_target(self.surface_format, blend),
+                1,
+                wgpu::PrimitiveState {
+                    topology,
+                    ..self.scene_primitive
+                },
+                self.scene_depth_stencil.clone(),
+            )
+        };
+        let pipelines = [
+            create(BlendState::REPLACE),
+            create(BlendState::ALPHA_BLENDING),
+        ];
+        self.topology_pipelines.insert(topology, pipelines);
+        Ok(())
+    }
+
+    /// Open another `width` x `height` window showing the scene, e.g. a
+    /// preview next to the main view. It is drawn with the same device
+    /// and camera, with its own aspect ratio, after the main window. The
+    /// post process and text passes are only done on the main window.
+    /// Return the SDL window id, found in `Event::Window::window_id`.
+    /// Closing the window drop it. Closing the main window hide it while
+    /// added windows are open, the app quit when the last one is closed.
+    pub fn add_window(&mut self, title: &str, width: u32, height: u32) -> Result<u32, String> {
+        let (source, sdl_ctx) = match (&self.surface_source, &self.sdl_ctx) {
+            (Some(source), Some(sdl_ctx)) => (source, sdl_ctx),
+            _ => return Err("Only desktop windowed XApp can add windows".to_string()),
+        };
+        let window = sdl_ctx
+            .video()?
+            .window(title, width, height)
+            .position_centered()
+            .resizable()
+            .allow_highdpi()
+            .build()
+            .map_err(|e| e.to_string())?;
+        let size = drawable_size(&window);
+        let surface = WindowSurface::new(source, &self.device, window, &self.config, size)?;
+        let id = surface.id();
+        self.windows.push(surface);
+        Ok(id)
+    }
+
+    /// Close window `id` opened with `add_window`. Return false when it
+    /// doesn't exist.
+    pub fn close_window(&mut self, id: u32) -> bool {
+        let before = self.windows.len();
+        self.windows.retain(|x| x.id() != id);
+        self.windows.len() != before
+    }
+
+    /// Ids of the windows opened with `add_window`, main window excluded.
+    pub fn window_ids(&self) -> Vec<u32> {
+        self.windows.iter().map(|x| x.id()).collect()
+    }
+
+    /// Resize the added window `id`.
+    fn handle_window_event(&mut self, id: u32, event: &WindowEvent) {
+        match event {
+            WindowEvent::SizeChanged(..)
+            | WindowEvent::DisplayChanged(_)
+            | WindowEvent::ICCProfChanged => {
+                if let Some(surface) = self.windows.iter_mut().find(|x| x.id() == id) {
+                    let (width, height) = drawable_size(surface.window());
+                    if (width, height) != surface.size() {
+                        surface.resize(&self.device, width, height);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Switch present mode at runtime, e.g. `Fifo` for vsync and
+    /// `Immediate` without it. Fail when the surface doesn't support
+    /// `mode` or on headless app. Cycled with `V` key.
+    pub fn set_present_mode(&mut self, mode: PresentMode) -> Result<(), String> {
+        let surface = match &self.target {
+            RenderTarget::Surface(x) => x,
+            RenderTarget::Offscreen(_) | RenderTarget::Released => {
+                return Err("XApp has no surface to set present mode".to_string())
+            }
+        };
+        if !self.surface_capabilities.present_modes.contains(&mode) {
+            return Err(format!("Present mode {:?} is not supported", mode));
+        }
+        self.config.present_mode = mode;
+        surface.configure(&self.device, &self.config);
+        #[cfg(debug_assertions)]
+        info!("Present mode: {:?}", mode);
+        Ok(())
+    }
+
+    pub fn present_mode(&self) -> PresentMode {
+        self.config.present_mode
+    }
+
+    /// Change how many frames can be queued ahead of the GPU, clamped to
+    /// `1..=3`, see `XAppBuilder::max_frame_latency`. Per-frame resources
+    /// are allocated for the highest latency, so raising it is safe.
+    pub fn set_max_frame_latency(&mut self, frames: u32) {
+        self.config.desired_maximum_frame_latency = clamp_frame_latency(frames);
+        if let RenderTarget::Surface(surface) = &self.target {
+            surface.configure(&self.device, &self.config);
+        }
+        #[cfg(debug_assertions)]
+        info!(
+            "Maximum frame latency: {}",
+            self.config.desired_maximum_frame_latency
+        );
+    }
+
+    pub fn max_frame_latency(&self) -> u32 {
+        self.config.desired_maximum_frame_latency
+    }
+
+    /// Set how following frames start, clearing to `DEFAULT_CLEAR_COLOR`
+    /// by default. Can be changed every frame from `Game::render`.
+    pub fn set_scene_pass(&mut self, pass: ScenePass) {
+        self.scene_pass = pass;
+    }
+
+    pub fn scene_pass(&self) -> ScenePass {
+        self.scene_pass
+    }
+
+    /// Record `pass` every frame, in its own encoder, after the passes of
+    /// lower `order` and those of the same order added before it. The
+    /// built-in `compute`, `scene`, `post` and `text` passes are at
+    /// `COMPUTE_PASS_ORDER`, `SCENE_PASS_ORDER`, `POST_PASS_ORDER` and
+    /// `TEXT_PASS_ORDER`. Fail when a pass is already named `name`.
+    pub fn add_pass(
+        &mut self,
+        name: &str,
+        order: i32,
+        pass: Box<dyn FramePass>,
+    ) -> Result<(), String> {
+        self.passes.get_mut().add(name, order, pass)
+    }
+
+    /// Stop recording the pass added as `name` and give it back. Built-in
+    /// passes can't be removed.
+    pub fn remove_pass(&mut self, name: &str) -> Option<Box<dyn FramePass>> {
+        self.passes.get_mut().remove(name)
+    }
+
+    /// Names of every pass, built-in ones included, in recording order.
+    pub fn pass_names(&self) -> Vec<String> {
+        self.passes.borrow().names()
+    }
+
+    /// Value written by `RenderObject::stencil_mask` objects and compared
+    /// by the builder stencil state.
+    pub fn set_stencil_reference(&mut self, reference: u32) {
+        self.stencil_reference = reference;
+    }
+
+    fn object_pipeline(&self, object: &RenderObject) -> &RenderPipeline {
+        let transparent = object.transparent;
+        if let Some(pipelines) = self.topology_pipelines.get(&object.topology()) {
+            if !object.stencil_mask {
+                return &pipelines[transparent as usize];
+            }
+        }
+        match &self.wireframe_pipeline {
+            _ if object.stencil_mask => &self.mask_pipeline,
+            Some(x) if self.wireframe => x,
+            _ if transparent => &self.alpha_pipeline,
+            _ => &self.pipeline,
+        }
+    }
+
+    /// Bind group layouts created and reused so far.
+    #[cfg(debug_assertions)]
+    pub fn layout_cache_stats(&self) -> LayoutCacheStats {
+        self.layouts.stats()
+    }
+
+    /// Index of the per-frame resource set used by the last frame, cycling
+    /// through `0..n` with `n` the maximum frame latency.
+    pub fn frame_index(&self) -> usize {
+        self.frames.index()
+    }
+
+    /// Device shared with the app, to create custom buffers and pipelines.
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    pub fn queue(&self) -> &Queue {
+        &self.queue
+    }
+
+    /// Format of the surface, or of the offscreen texture when headless.
+    /// A linear surface is viewed in its sRGB variant when the adapter
+    /// allow it, this is then the sRGB format. Pipelines drawing to the
+    /// output must target this format.
+    pub fn surface_format(&self) -> TextureFormat {
+        self.surface_format
+    }
+
+    /// Formats, present modes and alpha modes supported by the main window
+    /// surface. Empty for headless app.
+    pub fn surface_capabilities(&self) -> &SurfaceCapabilitiesSummary {
+        &self.surface_capabilities
+    }
+
+    /// Format, present mode and alpha mode currently used by the output.
+    pub fn surface_selection(&self) -> SurfaceSelection {
+        SurfaceSelection {
+            format: self.config.format,
+            present_mode: self.config.present_mode,
+            alpha_mode: self.config.alpha_mode,
+        }
+    }
+
+    /// Whether output format is sRGB, so the hardware encode linear shader
+    /// output. Colors written to a linear format should be gamma corrected
+    /// by the shader to look right.
+    pub fn is_srgb(&self) -> bool {
+        self.surface_format.is_srgb()
+    }
+
+    /// Whether the scene shader apply gamma 2.2 to its output, done when
+    /// the output format is linear. Custom pipelines drawing to a linear
+    /// output should do the same.
+    pub fn gamma_correction(&self) -> bool {
+        self.gamma_correction
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    pub fn is_minimized(&self) -> bool {
+        self.minimized
+    }
+
+    /// Hide the cursor and keep it in the window, only relative motion is
+    /// reported in `InputState::mouse_delta`. Toggled with `F2` key and
+    /// disabled when the window lose focus.
+    pub fn set_relative_mouse(&mut self, on: bool) {
+        if let Some(sdl) = &self.sdl_ctx {
+            sdl.mouse().set_relative_mouse_mode(on);
+            self.input.set_relative_mouse(on);
+        }
+    }
+
+    /// Start or stop text entry. While on, typed text, IME composition
+    /// included, is collected in `take_text_input`, and mobile show their
+    /// on-screen keyboard. Off by default.
+    pub fn set_text_input(&mut self, on: bool) {
+        if let Some(sdl) = &self.sdl_ctx {
+            let text_input = sdl.video().map(|video| video.text_input());
+            match text_input {
+                Ok(text_input) if on => text_input.start(),
+                Ok(text_input) => text_input.stop(),
+                Err(e) => warn!("Cannot change text input: {}", e),
+            }
+        }
+        self.input.set_text_input(on);
+    }
+
+    /// Rumble player 1 controller for `duration_ms`, `low_freq` and
+    /// `high_freq` are the strength of the low and high frequency motors.
+    /// Does nothing without controller or when it can't rumble.
+    pub fn rumble(&mut self, low_freq: u16, high_freq: u16, duration_ms: u32) {
+        self.controllers.rumble(low_freq, high_freq, duration_ms);
+    }
+
+    /// Player 1 controller, the first one connected.
+    pub fn controller(&self) -> Option<&sdl2::controller::GameController> {
+        self.controllers.player1()
+    }
+
+    /// Text typed since the last call while text entry is on, see
+    /// `set_text_input`.
+    pub fn take_text_input(&mut self) -> String {
+        self.input.take_text_input()
+    }
+
+    fn apply_title(&mut self) -> Result<(), String> {
+        #[cfg(not(target_os = "android"))]
+        if let Some(window) = &mut self.window {
+            let title = if self.show_fps_in_title {
+                format!("{} - {:.0} FPS", self.title, self.fps_counter.fps())
+            } else {
+                self.title.clone()
+            };
+            window.set_title(&title).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    /// Run event loop until quit, calling `game` every frame.
+    pub fn run(&mut self, game: &mut dyn Game) -> Result<(), String> {
+        let mut event_pump = match &self.sdl_ctx {
+            Some(x) => x.event_pump()?,
+            None => return Err("Headless XApp has no event loop".to_string()),
+        };
+
+        let result = self.run_loop(&mut event_pump, game);
+        // saved under the builder title it is loaded with, `set_title`
+        // may have changed the shown one since
+        if self.remember_window_state {
+            let state = self.window.as_ref().and_then(WindowState::from_window);
+            if let (Some(state), Some(builder)) = (state, &self.builder) {
+                state.save(&builder.title);
+            }
+        }
+        // let in-flight submissions finish before anything get dropped
+        self.wait_idle();
+        result
+    }
+
+    /// Run event loop until quit, calling `frame` with the frame time in
+    /// seconds before each render. Lighter than implementing `Game` for
+    /// quick experiments.
+    pub fn run_with<F: FnMut(&mut XApp, f32)>(&mut self, frame: F) -> Result<(), String> {
+        self.run(&mut FrameFn(frame))
+    }
+
+    /// Replace the clock giving frame time to `run`, e.g. `MockClock` for
+    /// a fixed `dt` every frame. The frame limiter keep using wall time.
+    pub fn set_clock(&mut self, clock: Box<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    /// Time the last frame took, in seconds.
+    pub fn frame_time(&self) -> f32 {
+        self.frame_time
+    }
+
+    /// Block until the GPU finish every submitted work.
+    pub fn wait_idle(&self) {
+        self.device.poll(wgpu::Maintain::Wait);
+    }
+
+    /// Reason the GPU device was lost, `None` while it works.
+    pub fn device_lost(&self) -> Option<String> {
+        self.device_lost.lock().ok().and_then(|x| x.clone())
+    }
+
+    /// Replace the whole app with a new one built from the same builder
+    /// options, with a new window, device and default scene.
+    fn recreate(&mut self) -> Result<(), String> {
+        let builder = self
+            .builder
+            .clone()
+            .ok_or("Headless XApp can't recreate its device")?;
+        self.recreate_with(&builder)
+            .map_err(|e| format!("Cannot recreate XApp after device lost: {}", e))
+    }
+
+    /// The new app is built first, on failure the current one is left
+    /// untouched.
+    fn recreate_with(&mut self, builder: &XAppBuilder) -> Result<(), String> {
+        let new = XApp::with_window(builder)?;
+        self.release_surface();
+        self.window = None;
+        *self = new;
+        Ok(())
+    }
+
+    /// Drop the main window surface and hide the window, added windows
+    /// keep drawing the scene.
+    fn hide_main_window(&mut self) {
+        self.wait_idle();
+        self.target = RenderTarget::Released;
+        if let Some(window) = &mut self.window {
+            window.hide();
+        }
+        #[cfg(debug_assertions)]
+        info!("Main window closed");
+    }
+
+    /// Wait for the GPU then drop the surface while the window still exist.
+    fn release_surface(&mut self) {
+        self.wait_idle();
+        self.windows.clear();
+        self.target = RenderTarget::Released;
+        #[cfg(debug_assertions)]
+        info!("Surface released");
+    }
+
+    /// Wait for the GPU then release every resource, surface before window.
+    pub fn shutdown(self) {
+        self.wait_idle();
+        #[cfg(debug_assertions)]
+        info!("XApp shutdown");
+        drop(self);
+    }
+
+    fn run_loop(&mut self, event_pump: &mut EventPump, game: &mut dyn Game) -> Result<(), String> {
+        let mut last_frame = self.clock.now();
+        let mut accumulator = 0.0;
+        'run: loop {
+            self.input.begin_frame();
+            // idle on-demand app sleep until the next event
+            let waited = if self.on_demand && !self.needs_redraw {
+                event_pump.wait_event_timeout(IDLE_WAIT_MS)
+            } else {
+                None
+            };
+            for event in waited.into_iter().chain(event_pump.poll_iter()) {
+                self.needs_redraw = true;
+                self.input
+                    .handle_event(&event, (self.config.width, self.config.height));
+                self.controllers.handle_event(&event);
+                if let Event::KeyDown {
+                    keycode: Some(code),
+                    ..
+                } = event
+                {
+                    // letters and Tab are typed text while text entry is
+                    // on, and with Ctrl or Alt they are the game shortcuts
+                    let typing = self.input.is_text_input()
+                        || self.input.is_ctrl_down()
+                        || self.input.is_alt_down();
+                    if code == Keycode::Tab && !typing {
+                        self.set_wireframe(!self.wireframe);
+                    }
+                    #[cfg(debug_assertions)]
+                    if code == Keycode::P && !typing {
+                        self.set_paused(!self.paused);
+                    }
+                    if code == Keycode::F2 {
+                        self.set_relative_mouse(!self.input.is_relative_mouse());
+                    }
+                    if code == Keycode::F3 && self.debug_overlay {
+                        self.show_overlay = !self.show_overlay;
+                    }
+                    if code == Keycode::F12 {
+                        let path = screenshot_file_name();
+                        match self.save_screenshot(Path::new(&path)) {
+                            Ok(()) => {
+                                #[cfg(debug_assertions)]
+                                info!("Screenshot saved to {}", path);
+                            }
+                            Err(e) => warn!("Cannot save screenshot: {}", e),
+                        }
+                    }
+                    if code == Keycode::V && !typing {
+                        if let Some(mode) = next_present_mode(
+                            self.config.present_mode,
+                            &self.surface_capabilities.present_modes,
+                        ) {
+                            self.set_present_mode(mode)?;
+                        }
+                    }
+                    game.key(code);
+                }
+                match event {
+                    // close button of a window, Quit is only sent by the
+                    // OS, e.g. on Ctrl+C
+                    Event::Window {
+                        window_id,
+                        win_event: WindowEvent::Close,
+                        ..
+                    } if game.close_requested(self, window_id) => {
+                        if self.window.as_ref().is_some_and(|x| x.id() == window_id) {
+                            self.hide_main_window();
+                        } else {
+                            self.close_window(window_id);
+                        }
+                        if self.windows.is_empty() && matches!(self.target, RenderTarget::Released)
+                        {
+                            break 'run;
+                        }
+                    }
+                    Event::Window {
+                        window_id,
+                        ref win_event,
+                        ..
+                    } if self.windows.iter().any(|x| x.id() == window_id) => {
+                        self.handle_window_event(window_id, win_event);
+                    }
+                    Event::Quit { timestamp } => {
+                        #[cfg(debug_assertions)]
+                        {
+                            info!("Exiting XApp. Running for about {}", timestamp);
+                        }
+                        break 'run;
+                    }
+                    // android is killing the activity, the native window can
+                    // be gone before `run` return so release the surface now
+                    Event::AppTerminating {
+                        timestamp: _timestamp,
+                    } => {
+                        #[cfg(debug_assertions)]
+                        info!(
+                            "Terminating (onDestroy) XApp. Running for about {}",
+                            _timestamp
+                        );
+                        self.release_surface();
+                        break 'run;
+                    }
+                    Event::AppLowMemory { timestamp } => {
+                        warn!("Low memory (onLowMemory). Running for about {}", timestamp);
+                    }
+                    Event::AppWillEnterBackground {
+                        timestamp: _timestamp,
+                    } => {
+                        #[cfg(debug_assertions)]
+                        info!(
+                            "Will enter background (onPause) XApp. Running for about {}",
+                            _timestamp
+                        );
+                    }
+                    Event::AppDidEnterBackground {
+                        timestamp: _timestamp,
+                    } => {
+                        #[cfg(debug_assertions)]
+                        info!("Entered background XApp. Running for about {}", _timestamp);
+                    }
+                    Event::AppDidEnterForeground {
+                        timestamp: _timestamp,
+                    } => {
+                        #[cfg(debug_assertions)]
+                        info!("Entered foreground XApp. Running for about {}", _timestamp);
+                    }
+                    // Ctrl or Alt with Escape is left to the game
+                    Event::KeyDown {
+                        keycode: Some(Keycode::Escape),
+                        timestamp,
+                        ..
+                    } if !self.input.is_ctrl_down() && !self.input.is_alt_down() => {
+                        #[cfg(all(not(target_os = "android"), debug_assertions))]
+                        {
+                            info!(
+                                "Exiting XApp from escape key. Running for about {}",
+                                timestamp
+                            );
+                            break 'run;
+                        }
+                    }
+
+                    Event::AppWillEnterForeground { timestamp } => {
+                        #[cfg(debug_assertions)]
+                        {
+                            info!(
+                                "Will enter foreground (onResume) XApp. Running for about {}",
+                                timestamp
+                            );
+                        }
+
+                        #[cfg(target_os = "android")]
+                        {
+                            let inst = self.wgpu_intance;
+                            let inst = match inst.as_ref() {
+                                Some(x) => x,
+                                None => {
+                                    return Err("WGPU intance is empty".to_string());
+                                }
+                            };
+                            let _ = self.init_surface(inst)?;
+                        }
+                    }
+                    Event::Window {
+                        win_event: WindowEvent::SizeChanged(..),
+                        ..
+                    } => {
+                        self.queue_resize(&event);
+                    }
+                    Event::Window {
+                        win_event: WindowEvent::DisplayChanged(_) | WindowEvent::ICCProfChanged,
+                        ..
+                    } => {
+                        // moved to a display of another pixel density,
+                        // drawable size change without SizeChanged
+                        self.refresh_display();
+                    }
+                    Event::Window {
+                        win_event: WindowEvent::FocusGained,
+                        ..
+                    } => {
+                        self.focused = true;
+                    }
+                    Event::Window {
+                        win_event: WindowEvent::FocusLost,
+                        ..
+                    } => {
+                        self.focused = false;
+                        // give the cursor back to other windows
+                        self.set_relative_mouse(false);
+                    }
+                    Event::Window {
+                        win_event: WindowEvent::Minimized,
+                        ..
+                    } => {
+                        #[cfg(debug_assertions)]
+                        info!("Window minimized, rendering paused");
+                        self.minimized = true;
+                    }
+                    Event::Window {
+                        win_event: WindowEvent::Restored | WindowEvent::Maximized,
+                        ..
+                    } => {
+                        #[cfg(debug_assertions)]
+                        info!("Window restored, rendering resumed");
+                        self.minimized = false;
+                    }
+                    e => {
+                        #[cfg(debug_assertions)]
+                        info!("{:?}", e);
+                    }
+                }
+            }
+
+            if let Some(reason) = self.device_lost() {
+                warn!("GPU device lost ({}), recreating XApp", reason);
+                self.recreate()?;
+                self.pending_resize = None;
+                game.device_restored(self);
+                last_frame = self.clock.now();
+                self.needs_redraw = true;
+                continue;
+            }
+
+            self.apply_pending_resize();
+            self.poll_loader();
+
+            // nothing is visible, don't spin the GPU nor the CPU
+            if self.minimized {
+                std::thread::sleep(std::time::Duration::from_millis(50));
+                last_frame = self.clock.now();
+                continue;
+            }
+            // don't count idle time in the next frame dt either
+            if self.on_demand && !self.needs_redraw {
+                last_frame = self.clock.now();
+                continue;
+            }
+            self.needs_redraw = false;
+
+            let frame_start = std::time::Instant::now();
+            let now = self.clock.now();
+            if std::mem::take(&mut self.reset_frame_timer) {
+                last_frame = now;
+            }
+            let dt = (now - last_frame).max(0.0) as f32;
+            last_frame = now;
+            self.frame_time = dt;
+
+            self.update_camera_from_input(dt);
+
+            let timestep = self.fixed_timestep;
+            let alpha = if self.paused {
+                accumulator / timestep
+            } else {
+                // paused from inside an update stop the remaining ones too
+                fixed_updates(&mut accumulator, dt, timestep, || {
+                    game.update(self, timestep);
+                    !self.paused
+                })
+            };
+            game.render(self, alpha);
+            if self.show_overlay {
+                let overlay = self.overlay_text();
+                self.draw_text(&overlay, 8.0, 8.0);
+            }
+            self.render()?;
+
+            if self.fps_counter.tick() && self.show_fps_in_title {
+                self.apply_title()?;
+            }
+
+            self.limit_frame_rate(frame_start);
+        }
+        Ok(())
+    }
+
+    /// Draw the next frame of an app built with `XAppBuilder::on_demand`.
+    /// Events already request one, this is for changes made outside of
+    /// them, e.g. an animation still running.
+    pub fn request_redraw(&mut self) {
+        self.needs_redraw = true;
+    }
+
+    /// Wait until the frame started at `frame_start` last `1 / target_fps`.
+    /// Fifo already wait for vblank, so it is only done on other modes.
+    fn limit_frame_rate(&self, frame_start: std::time::Instant) {
+        let target_fps = match self.target_fps {
+            Some(x) if x > 0 && self.config.present_mode != PresentMode::Fifo => x,
+            _ => return,
+        };
+        let frame_end = frame_start + std::time::Duration::from_secs_f64(1.0 / target_fps as f64);
+        // sleep is coarse on some OS, spin the last millisecond
+        let spin = std::time::Duration::from_millis(1);
+        let remaining = frame_end.saturating_duration_since(std::time::Instant::now());
+        if remaining > spin {
+            std::thread::sleep(remaining - spin);
+        }
+        while std::time::Instant::now() < frame_end {
+            std::hint::spin_loop();
+        }
+    }
+
+    /// Diagnostic lines of the `F3` overlay.
+    fn overlay_text(&self) -> String {
+        let mut gpu = match self.last_gpu_frame_ms() {
+            Some(ms) => format!("{:.2} MS", ms),
+            None => "N/A".to_string(),
+        };
+        let prepass = self.gpu_timer.as_ref().and_then(|timer| {
+            Some((
+                timer.last_pass_ms(timer::DEPTH_PREPASS)?,
+                timer.last_pass_ms(timer::SCENE_PASS)?,
+            ))
+        });
+        if let Some((depth, scene)) = prepass {
+            gpu = format!("{} (DEPTH {:.2} + SCENE {:.2})", gpu, depth, scene);
+        }
+        format!(
+            "FPS: {:.0}\nCPU: {:.2} MS\nGPU: {}\nCULLED: {}/{}\nADAPTER: {}\nPRESENT: {:?}\nUPLOAD: {}",
+            self.fps(),
+            self.frame_time * 1000.0,
+            gpu,
+            self.culled.get(),
+            self.objects.len(),
+            self.adapter,
+            self.config.present_mode,
+            if self.staging_belt.is_some() {
+                "STAGING BELT"
+            } else {
+                "WRITE BUFFER"
+            }
+        )
+    }
+
+    fn render(&self) -> Result<(), String> {
+        let surface = match &self.target {
+            RenderTarget::Surface(x) => x,
+            RenderTarget::Offscreen(_) => return self.render_frame(),
+            RenderTarget::Released => {
+                // main window closed, upload what the added windows read
+                if !self.windows.is_empty() {
+                    self.frames.advance();
+                    self.submit([
+                        self.encode("Upload encoder", |encoder| self.upload_frame(encoder))
+                    ]);
+                    self.render_windows();
+                }
+                return Ok(());
+            }
+        };
+        let output = surface.get_current_texture().map_err(|e| e.to_string())?;
+
+        let view = self.output_view(&output.texture);
+        self.draw(&view);
+        output.present();
+        self.render_windows();
+
+        Ok(())
+    }
+
+    /// Draw the scene into every window added with `add_window`. The
+    /// camera buffer is shared, so each window is its own submission.
+    fn render_windows(&self) {
+        for surface in &self.windows {
+            let Some(output) = surface.current_texture() else {
+                continue;
+            };
+            let view = self.output_view(&output.texture);
+            let mut camera = self.camera;
+            camera.set_viewport(surface.size().0, surface.size().1);
+            let view_proj = self.view_proj(&camera);
+            self.queue.write_buffer(
+                &self.frames.current().camera_buffer,
+                0,
+                bytemuck::cast_slice(&view_proj),
+            );
+            let commands = self.encode("Window encoder", |encoder| {
+                self.record_scene_pass(
+                    encoder,
+                    &view,
+                    surface.depth_stencil_view(),
+                    &view_proj,
+                    None,
+                );
+            });
+            self.queue.submit([commands]);
+            output.present();
+        }
+    }
+
+    /// View of a swapchain or offscreen texture the pipelines draw to, in
+    /// `surface_format`, which is the sRGB view of a linear surface when
+    /// the surface has one.
+    fn output_view(&self, texture: &wgpu::Texture) -> TextureView {
+        texture.create_view(&wgpu::TextureViewDescriptor {
+            format: Some(self.surface_format),
+            ..Default::default()
+        })
+    }
+
+    /// Draw one frame into the offscreen texture of a headless app.
+    pub fn render_frame(&self) -> Result<(), String> {
+        match &self.target {
+            RenderTarget::Offscreen(texture) => {
+                let view = self.output_view(texture);
+                self.draw(&view);
+                Ok(())
+            }
+            RenderTarget::Surface(_) | RenderTarget::Released => {
+                Err("render_frame need headless XApp".to_string())
+            }
+        }
+    }
+
+    /// Draw current scene and read it back as tightly packed RGBA8 pixels,
+    /// row by row from the top left corner.
+    pub fn capture(&self) -> Result<Vec<u8>, String> {
+        let texture = create_offscreen_texture(&self.device, &self.config);
+        let view = self.output_view(&texture);
+        self.draw(&view);
+        self.read_pixels(&texture)
+    }
+
+    /// Copy `texture`, made by `create_offscreen_texture`, back to the CPU
+    /// as tightly packed RGBA8 pixels.
+    fn read_pixels(&self, texture: &wgpu::Texture) -> Result<Vec<u8>, String> {
+        let (width, height) = (self.config.width, self.config.height);
+        let bytes_per_pixel = self
+            .config
+            .format
+            .block_copy_size(None)
+            .ok_or("Cannot capture depth/stencil format")?;
+        if bytes_per_pixel != 4 {
+            return Err(format!(
+                "Capture of format {:?} is not supported",
+                self.config.format
+            ));
+        }
+        let unpadded_row = width * bytes_per_pixel;
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_row = unpadded_row.div_ceil(align) * align;
+
+        let readback = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("capture readback"),
+            size: (padded_row * height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("Capture encoder"),
+            });
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &readback,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            texture.size(),
+        );
+        self.queue.submit([encoder.finish()]);
+
+        let slice = readback.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |_| {});
+        self.device.poll(wgpu::Maintain::Wait);
+
+        let bgra = matches!(
+            self.config.format,
+            TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb
+        );
+        let mut pixels = Vec::with_capacity((unpadded_row * height) as usize);
+        {
+            let data = slice.get_mapped_range();
+            for row in data.chunks(padded_row as usize) {
+                pixels.extend_from_slice(&row[..unpadded_row as usize]);
+            }
+        }
+        readback.unmap();
+
+        if bgra {
+            for px in pixels.chunks_exact_mut(4) {
+                px.swap(0, 2);
+            }
+        }
+        Ok(pixels)
+    }
+
+    /// Draw current scene and write it to `path` as PNG. Pixels are saved
+    /// as shown on screen, on sRGB and linear output alike. Bound to `F12`.
+    pub fn save_screenshot(&self, path: &Path) -> Result<(), String> {
+        let pixels = self.capture()?;
+        image::save_buffer_with_format(
+            path,
+            &pixels,
+            self.config.width,
+            self.config.height,
+            image::ExtendedColorType::Rgba8,
+            image::ImageFormat::Png,
+        )
+        .map_err(|e| format!("Cannot write {}: {}", path.display(), e))
+    }
+
+    /// Record compute pass animating vertices, run before the render pass.
+    /// Does nothing unless enabled with `XAppBuilder::compute`.
+    fn dispatch_compute(&self, encoder: &mut wgpu::CommandEncoder) {
+        if let Some(compute) = &self.compute {
+            compute.dispatch(&self.queue, encoder, &self.objects);
+        }
+    }
+
+    /// Record and submit scene draw into `view`.
+    fn draw(&self, view: &wgpu::TextureView) {
+        // set used until the next frame, also by added windows
+        self.frames.advance();
+        if let Some(timer) = &self.gpu_timer {
+            timer.poll(&self.device);
+        }
+
+        // with post process, scene go to offscreen texture first
+        let scene_view = match &self.post_process {
+            Some(post) => post.scene_view(),
+            None => view,
+        };
+
+        // one encoder per pass, submitted together in order after the
+        // uploads they read
+        let mut command_buffers =
+            vec![self.encode("Upload encoder", |encoder| self.upload_frame(encoder))];
+        for pass in self.passes.borrow_mut().iter_mut() {
+            let commands = match &mut pass.kind {
+                PassKind::Compute => {
+                    self.encode("Compute encoder", |encoder| self.dispatch_compute(encoder))
+                }
+                PassKind::Scene => self.encode("Scene encoder", |encoder| {
+                    self.draw_scene(encoder, scene_view)
+                }),
+                PassKind::Post => match &self.post_process {
+                    Some(post) => {
+                        self.encode("Post process encoder", |encoder| post.draw(encoder, view))
+                    }
+                    None => continue,
+                },
+                PassKind::Text => match &self.text {
+                    Some(text) => self.encode("Text encoder", |encoder| {
+                        text.flush(
+                            &self.queue,
+                            encoder,
+                            view,
+                            self.config.width,
+                            self.config.height,
+                        )
+                    }),
+                    None => continue,
+                },
+                PassKind::Custom(custom) => self.encode(&pass.name, |encoder| {
+                    custom.record(&mut PassContext {
+                        device: &self.device,
+                        queue: &self.queue,
+                        encoder,
+                        scene_view,
+                        depth_stencil_view: &self.depth_stencil_view,
+                        view,
+                        format: self.surface_format,
+                        camera_bind_group: &self.frames.current().camera_bind_group,
+                        width: self.config.width,
+                        height: self.config.height,
+                    })
+                }),
+            };
+            command_buffers.push(commands);
+        }
+
+        self.submit(command_buffers);
+
+        if let Some(timer) = &self.gpu_timer {
+            timer.map();
+        }
+    }
+
+    /// Submit `command_buffers`, closing the staging belt chunks they copy
+    /// from before and reclaiming them after.
+    fn submit(&self, command_buffers: impl IntoIterator<Item = wgpu::CommandBuffer>) {
+        if let Some(belt) = &self.staging_belt {
+            belt.borrow_mut().finish();
+        }
+        self.queue.submit(command_buffers);
+        if let Some(belt) = &self.staging_belt {
+            belt.borrow_mut().recall();
+        }
+    }
+
+    /// Upload camera and model matrix of every object before recording
+    /// the passes, debug lines queued for this frame, and light and
+    /// instances not uploaded yet.
+    fn upload_frame(&self, encoder: &mut wgpu::CommandEncoder) {
+        self.upload(
+            encoder,
+            &self.frames.current().camera_buffer,
+            bytemuck::cast_slice(&self.view_proj(&self.camera)),
+        );
+        if !self.objects.is_empty() {
+            let data = self.frames.current().object_uniforms.data(&self.objects);
+            self.upload(
+                encoder,
+                self.frames.current().object_uniforms.buffer(),
+                &data,
+            );
+        }
+        if let Some((buffer, data)) = self.debug_lines.take() {
+            self.upload(encoder, buffer, &data);
+        }
+        self.upload_pending(encoder);
+    }
+
+    /// Upload light and instances changed since last frame, only set when
+    /// they go through the staging belt.
+    fn upload_pending(&self, encoder: &mut wgpu::CommandEncoder) {
+        if self.light_dirty.take() {
+            self.upload(encoder, &self.light_buffer, bytemuck::bytes_of(&self.light));
+        }
+        if let Some(data) = self.pending_instances.take() {
+            self.upload(encoder, &self.instance_buffer, &data);
+        }
+    }
+
+    /// Write `data` at the start of `buffer`, copied by `encoder` from the
+    /// staging belt when enabled.
+    fn upload(&self, encoder: &mut wgpu::CommandEncoder, buffer: &wgpu::Buffer, data: &[u8]) {
+        let (Some(belt), Some(size)) =
+            (&self.staging_belt, wgpu::BufferSize::new(data.len() as u64))
+        else {
+            self.queue.write_buffer(buffer, 0, data);
+            return;
+        };
+        belt.borrow_mut()
+            .write_buffer(encoder, buffer, 0, size, &self.device)
+            .copy_from_slice(data);
+    }
+
+    fn encode(
+        &self,
+        label: &str,
+        record: impl FnOnce(&mut wgpu::CommandEncoder),
+    ) -> wgpu::CommandBuffer {
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor { label: Some(label) });
+        record(&mut encoder);
+        encoder.finish()
+    }
+
+    /// Record the scene pass: objects, sprites, particles and debug lines
+    /// into `scene_view`.
+    fn draw_scene(&self, encoder: &mut wgpu::CommandEncoder, scene_view: &wgpu::TextureView) {
+        let culled = self.record_scene_pass(
+            encoder,
+            scene_view,
+            &self.depth_stencil_view,
+            &self.view_proj(&self.camera),
+            self.gpu_timer.as_ref(),
+        );
+        self.culled.set(culled);
+
+        if let Some(timer) = &self.gpu_timer {
+            timer.resolve(encoder);
+        }
+    }
+
+    /// Opaque objects drawn in the depth prepass, then with `Equal` depth.
+    fn is_prepassed(&self, object: &RenderObject) -> bool {
+        !object.stencil_mask
+            && !object.transparent
+            && object.topology() == PrimitiveTopology::TriangleList
+    }
+
+    /// Whether each object may be seen through `view_proj`. Every object
+    /// is visible without frustum culling or with several instances.
+    fn visible_objects(&self, view_proj: &[[f32; 4]; 4]) -> Vec<bool> {
+        let instance = match self.single_instance {
+            Some(x) if self.frustum_culling => x,
+            _ => return vec![true; self.objects.len()],
+        };
+        let frustum = Frustum::from_view_proj(view_proj);
+        self.objects
+            .iter()
+            .map(|object| {
+                // the shader apply the instance matrix first
+                let bounds = object::transform_bounds(object.local_bounds(), &instance);
+                let (min, max) = object::transform_bounds(bounds, &object.model_matrix);
+                frustum.intersects(min, max)
+            })
+            .collect()
+    }
+
+    /// Record the depth prepass: stencil masks then the depth of opaque
+    /// objects in `visible`, kept in `depth_stencil_view` for the color
+    /// pass.
+    fn record_depth_prepass(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        prepass: &DepthPrepass,
+        visible: &[bool],
+        depth_stencil_view: &wgpu::TextureView,
+        timestamp_writes: Option<wgpu::RenderPassTimestampWrites<'_>>,
+    ) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Depth Prepass"),
+            color_attachments: &[],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: depth_stencil_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(0),
+                    store: wgpu::StoreOp::Store,
+                }),
+            }),
+            occlusion_query_set: None,
+            timestamp_writes,
+        });
+        render_pass.set_bind_group(1, &self.frames.current().camera_bind_group, &[]);
+        render_pass.set_bind_group(2, &self.light_bind_group, &[]);
+        render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+        render_pass.set_stencil_reference(self.stencil_reference);
+
+        let objects = || self.objects.iter().enumerate().filter(|(i, _)| visible[*i]);
+        let masks = objects().filter(|(_, o)| o.stencil_mask);
+        let opaque = objects().filter(|(_, o)| self.is_prepassed(o));
+        let mut current = None;
+        for (index, object) in masks.chain(opaque) {
+            if current != Some(object.stencil_mask) {
+                current = Some(object.stencil_mask);
+                render_pass.set_pipeline(if object.stencil_mask {
+                    &prepass.mask
+                } else {
+                    &prepass.depth
+                });
+            }
+            render_pass.set_bind_group(
+                0,
+                self.frames.current().object_uniforms.bind_group(),
+                &[self.frames.current().object_uniforms.offset(index)],
+            );
+            if self.push_constants {
+                render_pass.set_push_constants(
+                    wgpu::ShaderStages::FRAGMENT,
+                    0,
+                    bytemuck::cast_slice(&object.tint),
+                );
+            }
+            render_pass.set_vertex_buffer(0, object.vertex_buffer.slice(..));
+            render_pass.set_index_buffer(object.index_buffer.slice(..), object.index_format);
+            validate::assert_object_draw(object, &self.instance_buffer, self.instance_count);
+            render_pass.draw_indexed(0..object.index_count, 0, 0..self.instance_count);
+        }
+    }
+
+    /// Record one pass drawing every object, sprites, particles then debug
+    /// lines into `color_view`, after the depth prepass when it is enabled.
+    /// Objects out of the `view_proj` frustum are skipped, return how
+    /// many.
+    fn record_scene_pass(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        color_view: &wgpu::TextureView,
+        depth_stencil_view: &wgpu::TextureView,
+        view_proj: &[[f32; 4]; 4],
+        timer: Option<&GpuTimer>,
+    ) -> u32 {
+        let visible = self.visible_objects(view_proj);
+        let prepass = self
+            .depth_prepass
+            .as_ref()
+            .filter(|_| self.draw_geometry && !self.wireframe);
+        if let Some(prepass) = prepass {
+            self.record_depth_prepass(
+                encoder,
+                prepass,
+                &visible,
+                depth_stencil_view,
+                timer.and_then(|timer| timer.timestamp_writes(timer::DEPTH_PREPASS)),
+            );
+        }
+        // depth and stencil of the prepass are tested, then dropped
+        let (depth_load, stencil_load) = match prepass {
+            Some(_) => (wgpu::LoadOp::Load, wgpu::LoadOp::Load),
+            None => (wgpu::LoadOp::Clear(1.0), wgpu::LoadOp::Clear(0)),
+        };
+        let timestamp_writes = timer.and_then(|timer| timer.timestamp_writes(timer::SCENE_PASS));
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: color_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: self.scene_pass.load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: depth_stencil_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: depth_load,
+                        store: wgpu::StoreOp::Discard,
+                    }),
+                    stencil_ops: Some(wgpu::Operations {
+                        load: stencil_load,
+                        store: wgpu::StoreOp::Discard,
+                    }),
+                }),
+                occlusion_query_set: None,
+                timestamp_writes,
+            });
+
+            // clear only, to tell surface issues from pipeline issues
+            if self.draw_geometry {
+                // first, every object is drawn over it
+                if let Some(skybox) = &self.skybox {
+                    skybox.write(&self.queue, view_proj);
+                    skybox.draw(&mut render_pass);
+                }
+                render_pass.set_bind_group(1, &self.frames.current().camera_bind_group, &[]);
+                render_pass.set_bind_group(2, &self.light_bind_group, &[]);
+                render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+
+                // stencil masks first, then opaque objects, then blend
+                // transparent ones over them. Masks are already written
+                // by the prepass
+                let objects = || self.objects.iter().enumerate().filter(|(i, _)| visible[*i]);
+                let masks = objects().filter(|(_, o)| o.stencil_mask && prepass.is_none());
+                let opaque = objects().filter(|(_, o)| !o.stencil_mask && !o.transparent);
+                let transparent = objects().filter(|(_, o)| !o.stencil_mask && o.transparent);
+                render_pass.set_stencil_reference(self.stencil_reference);
+                let mut current = None;
+                for (index, object) in masks.chain(opaque).chain(transparent) {
+                    let kind = (object.stencil_mask, object.transparent, object.topology());
+                    if current != Some(kind) {
+                        current = Some(kind);
+                        render_pass.set_pipeline(match prepass {
+                            Some(prepass) if self.is_prepassed(object) => &prepass.shade,
+                            _ => self.object_pipeline(object),
+                        });
+                    }
+                    render_pass.set_bind_group(
+                        0,
+                        self.frames.current().object_uniforms.bind_group(),
+                        &[self.frames.current().object_uniforms.offset(index)],
+                    );
+                    if self.push_constants {
+                        render_pass.set_push_constants(
+                            wgpu::ShaderStages::FRAGMENT,
+                            0,
+                            bytemuck::cast_slice(&object.tint),
+                        );
+                    }
+                    render_pass.set_vertex_buffer(0, object.vertex_buffer.slice(..));
+                    render_pass
+                        .set_index_buffer(object.index_buffer.slice(..), object.index_format);
+                    validate::assert_object_draw(
+                        object,
+                        &self.instance_buffer,
+                        self.instance_count,
+                    );
+                    render_pass.draw_indexed(0..object.index_count, 0, 0..self.instance_count);
+                }
+
+                self.sprites.draw(
+                    &mut render_pass,
+                    &self.frames.current().camera_bind_group,
+                    &self.textures,
+                );
+                self.particles
+                    .draw(&mut render_pass, &self.frames.current().camera_bind_group);
+                self.debug_lines
+                    .draw(&mut render_pass, &self.frames.current().camera_bind_group);
+            }
+        }
+        visible.iter().filter(|x| !**x).count() as u32
+    }
+}
+
+/// Content of the render mode uniform, `render_mode` in `shader.wgsl`.
+fn render_mode_uniform(mode: u32, gamma_correction: bool) -> [u32; 4] {
+    [mode, gamma_correction as u32, 0, 0]
+}
+
+/// `screenshot-<unix time in ms>.png` in the working directory.
+fn screenshot_file_name() -> String {
+    let millis = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |x| x.as_millis());
+    format!("screenshot-{}.png", millis)
+}
+
+/// Size of `window` in pixels. It is bigger than `Window::size`, which is
+/// in points, on HiDPI displays since the window is created with
+/// `allow_highdpi`. Fall back to `Window::size` when SDL can't tell.
+fn drawable_size(window: &Window) -> (u32, u32) {
+    match window.drawable_size() {
+        (0, 0) => window.size(),
+        size => size,
+    }
+}
+
+/// Whether `alpha_mode` show the desktop through transparent pixels.
+fn is_transparent(alpha_mode: wgpu::CompositeAlphaMode) -> bool {
+    matches!(
+        alpha_mode,
+        wgpu::CompositeAlphaMode::PreMultiplied | wgpu::CompositeAlphaMode::PostMultiplied
+    )
+}
+
+/// Alpha mode from builder, `Auto` when the surface doesn't support it.
+fn select_alpha_mode(
+    supported: &[wgpu::CompositeAlphaMode],
+    wanted: wgpu::CompositeAlphaMode,
+) -> wgpu::CompositeAlphaMode {
+    if wanted == wgpu::CompositeAlphaMode::Auto || supported.contains(&wanted) {
+        return wanted;
+    }
+    warn!(
+        "Alpha mode {:?} is not supported, only {:?}, using Auto",
+        wanted, supported
+    );
+    wgpu::CompositeAlphaMode::Auto
+}
+
+/// Display index from builder, display 0 when it doesn't exist.
+fn select_display(video: &sdl2::VideoSubsystem, display: i32) -> Result<i32, String> {
+    let count = video.num_video_displays()?;
+    if display < 0 || display >= count {
+        warn!(
+            "Display {} doesn't exist, only {} found, using display 0",
+            display, count
+        );
+        return Ok(0);
+    }
+    Ok(display)
+}
+
+/// Next of Fifo, Mailbox and Immediate after `current` supported by the
+/// surface, `None` when there is nothing else to switch to.
+fn next_present_mode(current: PresentMode, supported: &[PresentMode]) -> Option<PresentMode> {
+    const CYCLE: [PresentMode; 3] = [
+        PresentMode::Fifo,
+        PresentMode::Mailbox,
+        PresentMode::Immediate,
+    ];
+    let start = CYCLE.iter().position(|x| *x == current).unwrap_or(0);
+    (1..CYCLE.len())
+        .map(|i| CYCLE[(start + i) % CYCLE.len()])
+        .find(|x| supported.contains(x))
+}
+
+fn load_icon(bmp: &[u8]) -> Result<sdl2::surface::Surface<'static>, String> {
+    let mut rwops = sdl2::rwops::RWops::from_bytes(bmp)?;
+    sdl2::surface::Surface::load_bmp_rw(&mut rwops)
+}
+
+/// Discrete GPU of the backend of `adapter` when it is another kind, with
+/// a warning recommending it.
+fn find_discrete_adapter(adapter: &AdapterSummary) -> Option<AdapterSummary> {
+    if adapter.device_type == wgpu::DeviceType::DiscreteGpu {
+        return None;
+    }
+    let discrete = XApp::enumerate_adapters(adapter.backend.into())
+        .into_iter()
+        .find(|x| x.device_type == wgpu::DeviceType::DiscreteGpu)?;
+    warn!(
+        "Running on {:?} {}, discrete GPU {} is available, see \
+         XApp::switch_to_discrete_adapter",
+        adapter.device_type, adapter, discrete.name
+    );
+    Some(discrete)
+}
+
+fn clamp_frame_latency(frames: u32) -> u32 {
+    let clamped = frames.clamp(1, MAX_FRAME_LATENCY);
+    if clamped != frames {
+        warn!(
+            "Frame latency {} out of 1..={}, using {}",
+            frames, MAX_FRAME_LATENCY, clamped
+        );
+    }
+    clamped
+}
+
+/// Fixed timestep from builder, 1/60 when it isn't a positive number.
+fn valid_timestep(seconds: f32) -> f32 {
+    if seconds.is_finite() && seconds > 0.0 {
+        seconds
+    } else {
+        warn!("Invalid fixed timestep {}, using 1/60", seconds);
+        1.0 / 60.0
+    }
+}
+
+/// Add a frame lasting `dt` to `accumulator`, capped to `MAX_FRAME_TIME` so
+/// a long stall isn't caught up forever, then call `update` once per whole
+/// `timestep` until it return `false`. Return the interpolation alpha
+/// given to `Game::render`.
+fn fixed_updates(
+    accumulator: &mut f32,
+    dt: f32,
+    timestep: f32,
+    mut update: impl FnMut() -> bool,
+) -> f32 {
+    *accumulator += dt.min(MAX_FRAME_TIME);
+    while *accumulator >= timestep {
+        *accumulator -= timestep;
+        if !update() {
+            break;
+        }
+    }
+    *accumulator / timestep
+}
+
+/// Size the surface is resized to once after polling, the last
+/// `SizeChanged` of `event`s replace `pending` since dragging a window edge
+/// send many of them per frame. Event size is in points, `drawable` pixel
+/// size of the window is used when known.
+fn coalesce_resize(
+    pending: Option<(u32, u32)>,
+    event: &Event,
+    drawable: Option<(u32, u32)>,
+) -> Option<(u32, u32)> {
+    match event {
+        Event::Window {
+            win_event: WindowEvent::SizeChanged(w, h),
+            ..
+        } => Some(drawable.unwrap_or((*w as u32, *h as u32))),
+        _ => pending,
+    }
+}
+
+/// Make initial surface size valid for `Surface::configure`. Zero side is
+/// clamped to 1, fully zero size fall back to `DEFAULT_SURFACE_SIZE`.
+fn clamp_surface_size(width: u32, height: u32) -> (u32, u32) {
+    if width == 0 && height == 0 {
+        warn!(
+            "Window size is zero, using {}x{}",
+            DEFAULT_SURFACE_SIZE.0, DEFAULT_SURFACE_SIZE.1
+        );
+        return DEFAULT_SURFACE_SIZE;
+    }
+    (width.max(1), height.max(1))
+}
+
+/// Adapter chosen with the builder, else the high performance adapter
+/// picked by wgpu. An adapter must be able to present to `surface`.
+fn request_adapter(
+    instance: &Instance,
+    builder: &XAppBuilder,
+    surface: Option<&Surface>,
+) -> Result<wgpu::Adapter, String> {
+    if let Some(selection) = &builder.adapter {
+        let adapters = instance
+            .enumerate_adapters(select_backends(builder))
+            .into_iter()
+            .filter(|x| surface.is_none_or(|s| x.is_surface_supported(s)))
+            .collect::<Vec<_>>();
+        let found = match selection {
+            AdapterSelection::Index(i) => adapters.into_iter().nth(*i),
+            AdapterSelection::Name(name) => {
+                let name = name.to_lowercase();
+                adapters
+                    .into_iter()
+                    .find(|x| x.get_info().name.to_lowercase().contains(&name))
+            }
+        };
+        match found {
+            Some(x) => return Ok(x),
+            None => warn!("No adapter match {:?}, using the default one", selection),
+        }
+    }
+
+    let mut adapter_option = RequestAdapterOptions {
+        power_preference: wgpu::PowerPreference::HighPerformance,
+        compatible_surface: surface,
+        force_fallback_adapter: false,
+    };
+    if let Some(x) = pollster::block_on(instance.request_adapter(&adapter_option)) {
+        return Ok(x);
+    }
+
+    // broken driver or VM without GPU, try a software adapter
+    adapter_option.force_fallback_adapter = true;
+    match pollster::block_on(instance.request_adapter(&adapter_option)) {
+        Some(x) => {
+            warn!(
+                "No hardware adapter found, using software adapter {}",
+                x.get_info().name
+            );
+            Ok(x)
+        }
+        None => Err(format!(
+            "Cannot get adapter for backends {:?} with power preference {:?}, \
+             even with fallback adapter",
+            select_backends(builder),
+            adapter_option.power_preference
+        )),
+    }
+}
+
+/// Install `env_logger` on debug build unless disabled with the builder,
+/// at the builder log level when set, else from `RUST_LOG`. Already done
+/// when the device is recreated or by the embedding app.
+fn init_logger(builder: &XAppBuilder) {
+    if cfg!(debug_assertions) && builder.init_logger {
+        let mut logger = env_logger::Builder::from_default_env();
+        if let Some(level) = builder.log_level {
+            logger.filter_level(level);
+        }
+        let _ = logger.try_init();
+    }
+}
+
+/// Backends from builder, else from `WGPU_BACKEND` environment variable
+/// (comma list of `vulkan`, `dx12`, `metal`, `gl`), else the default.
+fn select_backends(builder: &XAppBuilder) -> Backends {
+    if let Some(backends) = builder.backends {
+        #[cfg(debug_assertions)]
+        info!("Targeting backend from builder: {:?}", backends);
+        return backends;
+    }
+
+    if let Some(backends) = wgpu::util::backend_bits_from_env() {
+        #[cfg(debug_assertions)]
+        info!("Targeting backend from WGPU_BACKEND: {:?}", backends);
+        return backends;
+    }
+
+    default_backends()
+}
+
+/// Primary backends (Vulkan, Metal, DX12, WebGPU) on debug and release
+/// build alike, so a debug build run on the same driver as release.
+///
+/// Debug build used to default to secondary backends (GL) to catch early
+/// the limits of the GLES devices the Android build target, but on many
+/// desktops that pick a software or GL adapter which is slow and behave
+/// unlike release. Ask for them with `XAppBuilder::backends` or
+/// `WGPU_BACKEND=gl`.
+fn default_backends() -> Backends {
+    #[cfg(debug_assertions)]
+    info!("Targeting default backend: Primary");
+    Backends::PRIMARY
+}
+
+/// Pick sRGB surface format, or linear one when builder ask for it.
+/// Fall back to the first format with a warning.
+fn select_surface_format(
+    formats: &[TextureFormat],
+    builder: &XAppBuilder,
+) -> Result<TextureFormat, String> {
+    let first = *formats
+        .first()
+        .ok_or("Surface is not supported by the adapter")?;
+    let wanted = formats
+        .iter()
+        .copied()
+        .find(|f| f.is_srgb() != builder.linear_format);
+    match wanted {
+        Some(x) => Ok(x),
+        None => {
+            if builder.linear_format {
+                warn!("No linear surface format, using {:?}", first);
+            } else {
+                warn!(
+                    "No sRGB surface format, using linear {:?} with an sRGB view or \
+                     shader gamma correction",
+                    first
+                );
+            }
+            Ok(first)
+        }
+    }
+}
+
+/// sRGB variant of linear `format` to view the surface with, so the
+/// hardware encode shader output even without an sRGB surface format.
+/// `None` when an sRGB format was found, linear output is wanted, or the
+/// adapter can't view the surface in another format.
+fn srgb_view_format(
+    adapter: &wgpu::Adapter,
+    format: TextureFormat,
+    builder: &XAppBuilder,
+) -> Option<TextureFormat> {
+    let srgb = format.add_srgb_suffix();
+    if builder.linear_format || format.is_srgb() || srgb == format {
+        return None;
+    }
+    let supported = adapter
+        .get_downlevel_capabilities()
+        .flags
+        .contains(wgpu::DownlevelFlags::SURFACE_VIEW_FORMATS);
+    #[cfg(debug_assertions)]
+    info!(
+        "sRGB view {:?} of linear surface {:?} supported: {}",
+        srgb, format, supported
+    );
+    supported.then_some(srgb)
+}
+
+/// Check compute shader support, log a warning when it's missing.
+fn compute_supported(adapter: &wgpu::Adapter) -> bool {
+    let supported = adapter
+        .get_downlevel_capabilities()
+        .flags
+        .contains(wgpu::DownlevelFlags::COMPUTE_SHADERS);
+    if !supported {
+        warn!("Adapter doesn't support compute shader, compute animation disabled");
+    }
+    supported
+}
+
+/// `wanted` anisotropy limit, 1 when the adapter can't filter
+/// anisotropically.
+fn max_anisotropy(adapter: &wgpu::Adapter, wanted: u16) -> u16 {
+    let supported = adapter
+        .get_downlevel_capabilities()
+        .flags
+        .contains(wgpu::DownlevelFlags::ANISOTROPIC_FILTERING);
+    #[cfg(debug_assertions)]
+    info!("Anisotropic filtering supported: {}", supported);
+    if supported {
+        wanted
+    } else {
+        1
+    }
+}
+
+fn request_device(adapter: &wgpu::Adapter, compute: bool) -> Result<(Device, Queue), String> {
+    // optional features, only enabled when adapter has them
+    let features = adapter.features()
+        & (Features::PUSH_CONSTANTS | Features::POLYGON_MODE_LINE | Features::TIMESTAMP_QUERY);
+    let lim = required_limits(adapter, features, compute);
+    #[cfg(debug_assertions)]
+    info!("Requested device limits: {:?}", lim);
+    let device_desc = DeviceDescriptor {
+        label: Some("Xapp Device"),
+        required_features: features,
+        required_limits: lim,
+        ..Default::default()
+    };
+    let device = pollster::block_on(adapter.request_device(&device_desc, None));
+    device.map_err(|e| e.to_string())
+}
+
+/// Start from limits every downlevel device supports and only raise what
+/// the app need, asking for `adapter.limits()` fails on some drivers.
+fn required_limits(adapter: &wgpu::Adapter, features: Features, compute: bool) -> wgpu::Limits {
+    // webgl2 defaults have no compute limit at all
+    let base = if adapter.get_info().backend == wgpu::Backend::Gl && !compute {
+        wgpu::Limits::downlevel_webgl2_defaults()
+    } else {
+        wgpu::Limits::downlevel_defaults()
+    };
+
+    // surface may be larger than the 2048 px of downlevel defaults
+    let mut lim = base.using_resolution(adapter.limits());
+    if features.contains(Features::PUSH_CONSTANTS) {
+        lim.max_push_constant_size = pipeline::PUSH_CONSTANT_SIZE;
+    }
+    lim
+}
+
+/// Depth stencil attachment of the scene pass, `width` x `height`.
+fn create_depth_stencil_view(device: &Device, width: u32, height: u32) -> wgpu::TextureView {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("depth stencil texture"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: pipeline::DEPTH_STENCIL_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+    texture.create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+/// Texture with the size and format of `config`, usable as render target
+/// and as copy source for readback.
+fn create_offscreen_texture(device: &Device, config: &SurfaceConfiguration) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("offscreen target"),
+        size: wgpu::Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: config.format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &config.view_formats,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clamp_surface_size_zero_sides() {
+        assert_eq!(clamp_surface_size(0, 0), DEFAULT_SURFACE_SIZE);
+        assert_eq!(clamp_surface_size(0, 480), (1, 480));
+        assert_eq!(clamp_surface_size(640, 0), (640, 1));
+        assert_eq!(clamp_surface_size(640, 480), (640, 480));
+    }
+
+    /// Headless app without its default object. Panics without an adapter,
+    /// tests using it are ignored by default, run them with
+    /// `cargo test -- --ignored` on a machine with a GPU.
+    fn headless_app(width: u32, height: u32) -> XApp<'static> {
+        let mut app = XApp::headless(&XAppBuilder::new("test"), width, height)
+            .expect("headless XApp need a GPU adapter");
+        app.objects.clear();
+        app
+    }
+
+    /// Pixels of the offscreen target kept between `render_frame` calls,
+    /// `capture` draws into a new texture every time.
+    fn offscreen_pixels(app: &XApp) -> Vec<u8> {
+        match &app.target {
+            RenderTarget::Offscreen(texture) => app.read_pixels(texture).unwrap(),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn load_pass_keep_previous_frame() {
+        let mut app = headless_app(8, 8);
+        app.set_scene_pass(ScenePass::clear(wgpu::Color::RED));
+        app.render_frame().unwrap();
+        let cleared = offscreen_pixels(&app);
+        assert!(cleared.chunks_exact(4).all(|px| px == [255, 0, 0, 255]));
+
+        app.set_scene_pass(ScenePass::load());
+        app.render_frame().unwrap();
+        assert_eq!(offscreen_pixels(&app), cleared);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn large_mesh_use_32_bit_indices() {
+        let mut app = headless_app(8, 8);
+        // strip of thin triangles, more vertices than 16 bit can index
+        let vertices: Vec<Vertex> = (0..70_000)
+            .map(|i| Vertex {
+                position: [(i / 2) as f32 * 1e-5 - 0.5, (i % 2) as f32 * 0.5, 0.0],
+                color: [1.0; 3],
+                normal: [0.0, 0.0, -1.0],
+            })
+            .collect();
+        let indices: Vec<u32> = (0..vertices.len() as u32 - 2)
+            .flat_map(|i| [i, i + 1, i + 2])
+            .collect();
+        let large = app.add_mesh(&vertices, &indices);
+        let small = app.add_mesh(&vertices[..3], &[0, 1, 2]);
+        assert_eq!(
+            app.object(large).unwrap().index_format,
+            wgpu::IndexFormat::Uint32
+        );
+        assert_eq!(
+            app.object(small).unwrap().index_format,
+            wgpu::IndexFormat::Uint16
+        );
+
+        app.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        app.render_frame().unwrap();
+        let error = pollster::block_on(app.device.pop_error_scope());
+        assert!(error.is_none(), "{:?}", error);
+    }
+
+    #[test]
+    fn mock_clock_fixed_updates_and_alpha() {
+        let clock = MockClock::new(0.1875);
+        let mut last = clock.now();
+        let mut accumulator = 0.0;
+        let mut frame = |clock: &MockClock| {
+            let now = clock.now();
+            let dt = (now - last) as f32;
+            last = now;
+            let mut updates = 0;
+            let alpha = fixed_updates(&mut accumulator, dt, 0.125, || {
+                updates += 1;
+                true
+            });
+            (updates, alpha)
+        };
+        // 1.5 then 2 timesteps of frame time, remainder carried over
+        assert_eq!(frame(&clock), (1, 0.5));
+        assert_eq!(frame(&clock), (2, 0.0));
+        assert_eq!(frame(&clock), (1, 0.5));
+        // a stall is capped to MAX_FRAME_TIME
+        clock.advance(1.0);
+        assert_eq!(frame(&clock), (2, 0.5));
+    }
+
+    #[test]
+    fn fixed_updates_stop_when_paused() {
+        let mut accumulator = 0.0;
+        let mut updates = 0;
+        let alpha = fixed_updates(&mut accumulator, 0.25, 0.0625, || {
+            updates += 1;
+            false
+        });
+        assert_eq!(updates, 1);
+        assert_eq!(alpha, 3.0);
+    }
+
+    fn size_changed(width: i32, height: i32) -> Event {
+        Event::Window {
+            timestamp: 0,
+            window_id: 1,
+            win_event: WindowEvent::SizeChanged(width, height),
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn resize_burst_reconfigure_once_per_frame() {
+        let mut app = headless_app(8, 8);
+        let mut reconfigures = 0;
+        for frame in 0..3 {
+            // a drag send many SizeChanged in the first frame only
+            if frame == 0 {
+                for i in 1..=5 {
+                    app.queue_resize(&size_changed(10 * i, 5 * i));
+                }
+            }
+            if app.apply_pending_resize() {
+                reconfigures += 1;
+            }
+            app.render_frame().unwrap();
+        }
+        assert_eq!(reconfigures, 1);
+        assert_eq!((app.config.width, app.config.height), (50, 25));
+        match &app.target {
+            RenderTarget::Offscreen(texture) => {
+                assert_eq!((texture.width(), texture.height()), (50, 25))
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn resize_events_coalesce_to_last_size() {
+        let mut events: Vec<Event> = (1..=5).map(|i| size_changed(100 * i, 50 * i)).collect();
+        events.push(Event::Quit { timestamp: 0 });
+        let pending = events
+            .iter()
+            .fold(None, |pending, event| coalesce_resize(pending, event, None));
+        assert_eq!(pending, Some((500, 250)));
+
+        let other = [Event::Quit { timestamp: 0 }];
+        assert_eq!(
+            other
+                .iter()
+                .fold(None, |pending, event| coalesce_resize(pending, event, None)),
+            None
+        );
+        // pixel size of a HiDPI window wins over the size in points
+        assert_eq!(
+            coalesce_resize(None, &size_changed(100, 50), Some((200, 100))),
+            Some((200, 100))
+        );
     }
 }
@@ -0,0 +1,363 @@
+//! Wavefront OBJ loading with `tobj`.
+//!
+//! Only geometry is read: positions, the `v x y z r g b` color extension
+//! and `vn` normals. Texture coordinates and materials are ignored, since
+//! `Vertex` has none. A position used with several normals becomes one
+//! vertex per normal. Meshes without a normal on every vertex get them
+//! all from `compute_normals`. Every `o`/`g` group is concatenated into
+//! one mesh, polygons are triangulated as a fan.
+//!
+//! OBJ is right handed, its viewer looking toward `-z`, while the camera
+//! is left handed, looking toward `+z`. `z` of positions and normals is
+//! negated, which bring the side modeled toward the viewer in front of
+//! the camera unmirrored. Triangles are then seen with the winding they
+//! were modeled with, counter clockwise for the default `Ccw` front face,
+//! so indices are kept as is.
+
+use super::Vertex;
+
+/// Color of vertices without the `r g b` extension.
+const DEFAULT_COLOR: [f32; 3] = [1.0, 1.0, 1.0];
+/// Normal of vertices not used by any face, facing the camera.
+const DEFAULT_NORMAL: [f32; 3] = [0.0, 0.0, -1.0];
+
+/// Parse OBJ `bytes` into vertices and triangle list indices.
+pub(crate) fn parse_obj(bytes: &[u8]) -> Result<(Vec<Vertex>, Vec<u32>), String> {
+    // materials are not used, `mtllib` files are never opened
+    let (models, _) = tobj::load_obj_buf(
+        &mut std::io::Cursor::new(bytes),
+        &tobj::GPU_LOAD_OPTIONS,
+        |_| Err(tobj::LoadError::OpenFileFailed),
+    )
+    .map_err(|e| format!("Cannot parse OBJ: {}", e))?;
+
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    for model in &models {
+        let offset = u32::try_from(vertices.len()).map_err(|_| {
+            format!(
+                "OBJ has {} vertices, more than 32 bit index can address",
+                vertices.len()
+            )
+        })?;
+        let (mesh_vertices, mesh_indices) = convert_mesh(&model.mesh);
+        vertices.extend(mesh_vertices);
+        indices.extend(mesh_indices.iter().map(|i| i + offset));
+    }
+    if indices.is_empty() {
+        return Err("OBJ has no face".to_string());
+    }
+    Ok((vertices, indices))
+}
+
+/// Vertices and indices of `mesh` in the left handed world of the camera.
+fn convert_mesh(mesh: &tobj::Mesh) -> (Vec<Vertex>, Vec<u32>) {
+    let has_colors = mesh.vertex_color.len() == mesh.positions.len();
+    let has_normals = mesh.normals.len() == mesh.positions.len();
+    let mut vertices: Vec<Vertex> = (0..mesh.positions.len() / 3)
+        .map(|i| {
+            let at = |data: &[f32]| [data[i * 3], data[i * 3 + 1], data[i * 3 + 2]];
+            Vertex {
+                position: to_left_handed(at(&mesh.positions)),
+                color: if has_colors {
+                    at(&mesh.vertex_color)
+                } else {
+                    DEFAULT_COLOR
+                },
+                normal: if has_normals {
+                    to_left_handed(at(&mesh.normals))
+                } else {
+                    DEFAULT_NORMAL
+                },
+            }
+        })
+        .collect();
+    if !has_normals {
+        compute_normals(&mut vertices, &mesh.indices);
+    }
+    (vertices, mesh.indices.clone())
+}
+
+/// Mirror right handed OBJ `[x, y, z]` along `z`.
+fn to_left_handed([x, y, z]: [f32; 3]) -> [f32; 3] {
+    [x, y, -z]
+}
+
+/// Set the normal of every vertex used by triangle list `indices` to the
+/// average of the normals of the triangles sharing it, each on the side
+/// it is seen counter clockwise from in the left handed world,
+/// weighted by their area, so a vertex shared by the faces of a cube
+/// corner point along the diagonal. Give the vertex its own copy per face
+/// for flat shading. Vertices used by no triangle, or only degenerate
+/// ones, keep their normal, triangles with an index out of `vertices` are
+/// skipped.
+pub fn compute_normals(vertices: &mut [Vertex], indices: &[u32]) {
+    let mut sums = vec![[0.0f32; 3]; vertices.len()];
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|i| i as usize);
+        let (Some(va), Some(vb), Some(vc)) = (vertices.get(a), vertices.get(b), vertices.get(c))
+        else {
+            continue;
+        };
+        let normal = triangle_normal(va.position, vb.position, vc.position);
+        for index in [a, b, c] {
+            for (sum, n) in sums[index].iter_mut().zip(normal) {
+                *sum += n;
+            }
+        }
+    }
+    for (vertex, sum) in vertices.iter_mut().zip(sums) {
+        if let Some(normal) = normalize(sum) {
+            vertex.normal = normal;
+        }
+    }
+}
+
+/// Normal of triangle `a`, `b`, `c`, not normalized, on the side it is
+/// seen counter clockwise from. The world is left handed like the camera,
+/// so it is `ac x ab` rather than `ab x ac`.
+fn triangle_normal(a: [f32; 3], b: [f32; 3], c: [f32; 3]) -> [f32; 3] {
+    let u = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+    let v = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+    [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ]
+}
+
+fn normalize(v: [f32; 3]) -> Option<[f32; 3]> {
+    let length = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    (length > 0.0).then(|| [v[0] / length, v[1] / length, v[2] / length])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::validate::check_winding;
+    use super::*;
+
+    fn parse(text: &str) -> Result<(Vec<Vertex>, Vec<u32>), String> {
+        parse_obj(text.as_bytes())
+    }
+
+    fn assert_near(a: [f32; 3], b: [f32; 3]) {
+        for axis in 0..3 {
+            assert!((a[axis] - b[axis]).abs() < 1e-5, "{:?} != {:?}", a, b);
+        }
+    }
+
+    /// Counter clockwise seen from `+z`, the outside in OBJ convention.
+    const TRIANGLE: &str = "v 0 0 0\nv 1 0 0\nv 0 1 0\n";
+
+    #[test]
+    fn quad_and_ngon_are_fan_triangulated() {
+        let (_, indices) = parse("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3 4\n").unwrap();
+        assert_eq!(indices, [0, 1, 2, 0, 2, 3]);
+
+        let (_, indices) =
+            parse("v 0 0 0\nv 1 0 0\nv 2 1 0\nv 1 2 0\nv 0 1 0\nf 1 2 3 4 5\n").unwrap();
+        assert_eq!(indices, [0, 1, 2, 0, 2, 3, 0, 3, 4]);
+    }
+
+    #[test]
+    fn negative_index_count_back_from_last_vertex() {
+        let (vertices, indices) =
+            parse("v 9 9 9\nv 0 0 0\nv 1 0 0\nv 0 1 0\nf -3 -2 -1\n").unwrap();
+        let positions: Vec<[f32; 3]> = vertices.iter().map(|v| v.position).collect();
+        assert_eq!(positions, [[0.0; 3], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]);
+        assert_eq!(indices, [0, 1, 2]);
+    }
+
+    #[test]
+    fn face_token_forms() {
+        let head = format!("{}vt 0 0\nvn 1 0 1\n", TRIANGLE);
+        for (face, normal) in [
+            ("f 1 2 3", [0.0, 0.0, -1.0]),
+            ("f 1/1 2/1 3/1", [0.0, 0.0, -1.0]),
+            ("f 1//1 2//1 3//1", [1.0, 0.0, -1.0]),
+            ("f 1/1/1 2/1/1 3/1/1", [1.0, 0.0, -1.0]),
+        ] {
+            let (vertices, indices) = parse(&format!("{}{}\n", head, face)).unwrap();
+            assert_eq!(indices, [0, 1, 2], "{}", face);
+            for vertex in &vertices {
+                assert_near(vertex.normal, normal);
+            }
+        }
+    }
+
+    #[test]
+    fn normal_defined_after_face() {
+        let (vertices, _) = parse(&format!("{}f 1//1 2//1 3//1\nvn 0 1 0\n", TRIANGLE)).unwrap();
+        for vertex in &vertices {
+            assert_near(vertex.normal, [0.0, 1.0, 0.0]);
+        }
+    }
+
+    #[test]
+    fn position_with_several_normals_is_split() {
+        let (vertices, indices) = parse(&format!(
+            "{}vn 0 0 1\nvn 0 1 0\nf 1//1 2//1 3//1\nf 1//2 3//2 2//2\n",
+            TRIANGLE
+        ))
+        .unwrap();
+        assert_eq!(vertices.len(), 6);
+        assert_eq!(indices.len(), 6);
+    }
+
+    #[test]
+    fn right_handed_model_face_the_camera() {
+        // seen from the OBJ default viewer at +z, toward the camera once
+        // mirrored
+        let (vertices, indices) = parse("v 0 0 1\nv 1 0 1\nv 0 1 1\nf 1 2 3\n").unwrap();
+        assert_eq!(vertices[0].position, [0.0, 0.0, -1.0]);
+        assert_near(vertices[0].normal, [0.0, 0.0, -1.0]);
+        const IDENTITY: [[f32; 4]; 4] = [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ];
+        let report = check_winding(
+            &vertices,
+            &indices,
+            &IDENTITY,
+            wgpu::FrontFace::Ccw,
+            Some(wgpu::Face::Back),
+        );
+        assert_eq!(report.front_facing, 1);
+
+        // the same face with its normal given agree
+        let (with_normal, _) =
+            parse("v 0 0 1\nv 1 0 1\nv 0 1 1\nvn 0 0 1\nf 1//1 2//1 3//1\n").unwrap();
+        assert_near(with_normal[0].normal, vertices[0].normal);
+    }
+
+    #[test]
+    fn closed_model_normals_point_outward() {
+        // cube with counter clockwise faces seen from outside, right handed
+        let mut text = String::new();
+        for i in 0..8 {
+            let [x, y, z] = [0, 1, 2].map(|j| if i >> j & 1 == 1 { 0.5 } else { -0.5 });
+            text += &format!("v {} {} {}\n", x, y, z);
+        }
+        for face in [
+            "1 3 4 2", "5 6 8 7", "1 5 7 3", "2 4 8 6", "1 2 6 5", "3 7 8 4",
+        ] {
+            text += &format!("f {}\n", face);
+        }
+        let (vertices, _) = parse(&text).unwrap();
+        for vertex in &vertices {
+            let p = vertex.position;
+            let n = vertex.normal;
+            assert!(
+                p[0] * n[0] + p[1] * n[1] + p[2] * n[2] > 0.0,
+                "{:?}",
+                vertex
+            );
+        }
+    }
+
+    #[test]
+    fn groups_are_concatenated() {
+        let (vertices, indices) = parse(
+            "o a\nv 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n\
+             o b\nv 0 0 1\nv 1 0 1\nv 0 1 1\nf 4 5 6\n",
+        )
+        .unwrap();
+        assert_eq!(vertices.len(), 6);
+        assert_eq!(indices, [0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn vertex_color_extension() {
+        let (vertices, _) =
+            parse("v 0 0 0 1 0 0\nv 1 0 0 0 1 0\nv 0 1 0 0 0 1\nf 1 2 3\n").unwrap();
+        assert_eq!(vertices[0].color, [1.0, 0.0, 0.0]);
+        let (vertices, _) = parse(&format!("{}f 1 2 3\n", TRIANGLE)).unwrap();
+        assert_eq!(vertices[0].color, DEFAULT_COLOR);
+    }
+
+    fn vertex(position: [f32; 3]) -> Vertex {
+        Vertex {
+            position,
+            color: DEFAULT_COLOR,
+            normal: DEFAULT_NORMAL,
+        }
+    }
+
+    #[test]
+    fn cube_corner_normals_point_along_diagonal() {
+        // corner i has coordinate bit j set to +1
+        let vertices: Vec<Vertex> = (0..8)
+            .map(|i| {
+                vertex(std::array::from_fn(|j| {
+                    if i >> j & 1 == 1 {
+                        1.0
+                    } else {
+                        -1.0
+                    }
+                }))
+            })
+            .collect();
+        let mut indices = Vec::new();
+        for axis in 0..3 {
+            for side in [0, 1] {
+                let corners: Vec<u32> = (0..8).filter(|i| i >> axis & 1 == side).collect();
+                // split along the diagonal of the even corners, so every
+                // corner has as many triangles on each of its faces
+                let (even, odd): (Vec<u32>, Vec<u32>) =
+                    corners.iter().partition(|i| i.count_ones() % 2 == 0);
+                for triangle in [[even[0], odd[0], even[1]], [even[1], odd[1], even[0]]] {
+                    let [a, b, c] = triangle.map(|i| vertices[i as usize].position);
+                    let outward = if side == 1 { 1.0 } else { -1.0 };
+                    if triangle_normal(a, b, c)[axis] * outward < 0.0 {
+                        indices.extend([triangle[0], triangle[2], triangle[1]]);
+                    } else {
+                        indices.extend(triangle);
+                    }
+                }
+            }
+        }
+        let mut normals = vertices.clone();
+        compute_normals(&mut normals, &indices);
+        let d = 1.0 / 3.0f32.sqrt();
+        for vertex in &normals {
+            assert_near(vertex.normal, vertex.position.map(|x| x * d));
+        }
+    }
+
+    #[test]
+    fn degenerate_and_out_of_range_triangles_keep_normal() {
+        let mut vertices = vec![
+            vertex([0.0, 0.0, 0.0]),
+            vertex([1.0, 0.0, 0.0]),
+            vertex([2.0, 0.0, 0.0]),
+        ];
+        for vertex in &mut vertices {
+            vertex.normal = [1.0, 0.0, 0.0];
+        }
+        compute_normals(&mut vertices, &[0, 1, 2, 0, 1, 3]);
+        for vertex in &vertices {
+            assert_eq!(vertex.normal, [1.0, 0.0, 0.0]);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        assert!(parse_obj(&[0xff, 0xfe, b'\n']).is_err());
+        let cases = [
+            ("v 0 0\n", "Cannot parse OBJ"),
+            ("v 0 0 x\n", "Cannot parse OBJ"),
+            ("vn 0 1\n", "Cannot parse OBJ"),
+            ("v 0 0 0\nv 1 0 0\nf 1 2 3\n", "Cannot parse OBJ"),
+            ("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 0\n", "Cannot parse OBJ"),
+            // lines are not faces
+            ("v 0 0 0\nv 1 0 0\nf 1 2\n", "no face"),
+            ("v 0 0 0\n", "no face"),
+        ];
+        for (text, message) in cases {
+            let error = parse(text).unwrap_err();
+            assert!(error.contains(message), "{:?}: {}", text, error);
+        }
+    }
+}
@@ -0,0 +1,312 @@
+use wgpu::{
+    util::DeviceExt, BindGroup, BindGroupLayout, Buffer, Device, IndexFormat, PrimitiveTopology,
+    Queue,
+};
+
+use super::{compute::ComputeBinding, Vertex};
+
+pub const IDENTITY_MATRIX: [[f32; 4]; 4] = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+/// Index buffer content of a mesh. 16 bit indices address at most 65536
+/// vertices, bigger meshes need `U32`.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Indices<'a> {
+    U16(&'a [u16]),
+    U32(&'a [u32]),
+}
+
+impl Indices<'_> {
+    fn format(&self) -> IndexFormat {
+        match self {
+            Indices::U16(_) => IndexFormat::Uint16,
+            Indices::U32(_) => IndexFormat::Uint32,
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Indices::U16(x) => x.len(),
+            Indices::U32(x) => x.len(),
+        }
+    }
+
+    /// Highest vertex index, `None` when empty.
+    fn max(&self) -> Option<u32> {
+        match self {
+            Indices::U16(x) => x.iter().max().map(|x| *x as u32),
+            Indices::U32(x) => x.iter().max().copied(),
+        }
+    }
+
+    fn bytes(&self) -> &[u8] {
+        match self {
+            Indices::U16(x) => bytemuck::cast_slice(x),
+            Indices::U32(x) => bytemuck::cast_slice(x),
+        }
+    }
+}
+
+/// Min and max corners of the box around `vertices`, both at the origin
+/// when there are none.
+fn vertex_bounds(vertices: &[Vertex]) -> ([f32; 3], [f32; 3]) {
+    if vertices.is_empty() {
+        return ([0.0; 3], [0.0; 3]);
+    }
+    vertices
+        .iter()
+        .fold(([f32::MAX; 3], [f32::MIN; 3]), |(min, max), v| {
+            (
+                std::array::from_fn(|i| min[i].min(v.position[i])),
+                std::array::from_fn(|i| max[i].max(v.position[i])),
+            )
+        })
+}
+
+/// Box aligned on the axes around the 8 corners of `bounds` moved by
+/// column major `matrix`.
+pub(crate) fn transform_bounds(
+    (min, max): ([f32; 3], [f32; 3]),
+    matrix: &[[f32; 4]; 4],
+) -> ([f32; 3], [f32; 3]) {
+    let mut moved = ([f32::MAX; 3], [f32::MIN; 3]);
+    for i in 0..8 {
+        let corner = [
+            if i & 1 == 0 { min[0] } else { max[0] },
+            if i & 2 == 0 { min[1] } else { max[1] },
+            if i & 4 == 0 { min[2] } else { max[2] },
+        ];
+        let point: [f32; 3] = std::array::from_fn(|axis| {
+            matrix[0][axis] * corner[0]
+                + matrix[1][axis] * corner[1]
+                + matrix[2][axis] * corner[2]
+                + matrix[3][axis]
+        });
+        moved.0 = std::array::from_fn(|axis| moved.0[axis].min(point[axis]));
+        moved.1 = std::array::from_fn(|axis| moved.1[axis].max(point[axis]));
+    }
+    moved
+}
+
+/// Uniform data of one object, matches `ObjectUniform` in shader.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct ObjectUniform {
+    model: [[f32; 4]; 4],
+    tint: [f32; 4],
+    opacity: f32,
+    _padding: [f32; 3],
+}
+
+pub struct RenderObject {
+    pub vertex_buffer: Buffer,
+    pub index_buffer: Buffer,
+    pub index_count: u32,
+    /// `Uint32` for objects added with `XApp::add_object_u32` or OBJ files
+    /// with more than 65536 vertices.
+    pub index_format: IndexFormat,
+    pub model_matrix: [[f32; 4]; 4],
+    /// Color multiplied with vertex color. Sent with push constants when
+    /// the device supports it, otherwise with the object uniform.
+    pub tint: [f32; 4],
+    /// Alpha of the object, only visible when `transparent` is set.
+    pub opacity: f32,
+    /// Draw object with alpha blending pipeline. Transparent objects are
+    /// drawn after every opaque object, in the order they were added, so
+    /// they are composited over the opaque scene. Add transparent objects
+    /// back to front for correct result when they overlap each other.
+    pub transparent: bool,
+    /// Write the stencil reference where the object cover instead of
+    /// drawing it. Mask objects are drawn before every other object, then
+    /// the builder stencil state test against what they wrote.
+    pub stencil_mask: bool,
+
+    pub(crate) compute: Option<ComputeBinding>,
+    pub(crate) topology: PrimitiveTopology,
+    bounds: ([f32; 3], [f32; 3]),
+}
+
+impl RenderObject {
+    pub(crate) fn new(
+        device: &Device,
+        vertices: &[Vertex],
+        indices: Indices,
+        vertex_usage: wgpu::BufferUsages,
+    ) -> Self {
+        if let Some(max) = indices.max() {
+            debug_assert!(
+                (max as usize) < vertices.len(),
+                "Index {} is past the last of {} vertices",
+                max,
+                vertices.len()
+            );
+        }
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("object vertex buffer"),
+            contents: bytemuck::cast_slice(vertices),
+            // written in place by `XApp::set_vertex_color`
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST | vertex_usage,
+        });
+
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("object index buffer"),
+            contents: indices.bytes(),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        RenderObject {
+            vertex_buffer,
+            index_buffer,
+            index_count: indices.len() as u32,
+            index_format: indices.format(),
+            model_matrix: IDENTITY_MATRIX,
+            tint: [1.0; 4],
+            opacity: 1.0,
+            transparent: false,
+            stencil_mask: false,
+            compute: None,
+            topology: PrimitiveTopology::TriangleList,
+            bounds: vertex_bounds(vertices),
+        }
+    }
+
+    /// Number of `Vertex` the vertex buffer hold.
+    pub fn vertex_count(&self) -> usize {
+        self.vertex_buffer.size() as usize / std::mem::size_of::<Vertex>()
+    }
+
+    /// Min and max corners of the box around the vertices as they were
+    /// added, before `model_matrix`. Vertices moved later by the compute
+    /// pass aren't followed.
+    pub fn local_bounds(&self) -> ([f32; 3], [f32; 3]) {
+        self.bounds
+    }
+
+    /// `local_bounds` moved by `model_matrix`, still aligned on the world
+    /// axes so it can grow when the object is rotated. Instances aren't
+    /// included.
+    pub fn bounds(&self) -> ([f32; 3], [f32; 3]) {
+        transform_bounds(self.bounds, &self.model_matrix)
+    }
+
+    /// How indices are assembled, set with `XApp::set_topology`.
+    pub fn topology(&self) -> PrimitiveTopology {
+        self.topology
+    }
+
+    pub(crate) fn uniform(&self) -> ObjectUniform {
+        ObjectUniform {
+            model: self.model_matrix,
+            tint: self.tint,
+            opacity: self.opacity,
+            _padding: [0.0; 3],
+        }
+    }
+}
+
+/// Uniforms of every object in one buffer, each at its own dynamic offset
+/// of a single bind group.
+pub(crate) struct ObjectUniforms {
+    buffer: Buffer,
+    bind_group: BindGroup,
+    // uniform size rounded up to `min_uniform_buffer_offset_alignment`
+    stride: u64,
+    capacity: usize,
+}
+
+impl ObjectUniforms {
+    pub(crate) fn new(device: &Device, layout: &BindGroupLayout, capacity: usize) -> Self {
+        let alignment = device.limits().min_uniform_buffer_offset_alignment as u64;
+        let size = std::mem::size_of::<ObjectUniform>() as u64;
+        let stride = size.div_ceil(alignment) * alignment;
+        let capacity = capacity.max(1);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("object uniforms"),
+            size: stride * capacity as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("object uniforms bind group"),
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &buffer,
+                    offset: 0,
+                    size: wgpu::BufferSize::new(size),
+                }),
+            }],
+        });
+
+        ObjectUniforms {
+            buffer,
+            bind_group,
+            stride,
+            capacity,
+        }
+    }
+
+    /// Make room for `count` objects, the buffer grow by doubling.
+    pub(crate) fn reserve(&mut self, device: &Device, layout: &BindGroupLayout, count: usize) {
+        if count > self.capacity {
+            *self = Self::new(device, layout, count.next_power_of_two());
+        }
+    }
+
+    /// Upload uniform of every object with one write.
+    pub(crate) fn write(&self, queue: &Queue, objects: &[RenderObject]) {
+        if objects.is_empty() {
+            return;
+        }
+        queue.write_buffer(&self.buffer, 0, &self.data(objects));
+    }
+
+    /// Content of the buffer for `objects`, one stride per object.
+    pub(crate) fn data(&self, objects: &[RenderObject]) -> Vec<u8> {
+        let mut data = vec![0u8; self.stride as usize * objects.len()];
+        for (chunk, object) in data.chunks_mut(self.stride as usize).zip(objects) {
+            let uniform = object.uniform();
+            let bytes = bytemuck::bytes_of(&uniform);
+            chunk[..bytes.len()].copy_from_slice(bytes);
+        }
+        data
+    }
+
+    pub(crate) fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    pub(crate) fn bind_group(&self) -> &BindGroup {
+        &self.bind_group
+    }
+
+    /// Dynamic offset of object at `index`.
+    pub(crate) fn offset(&self, index: usize) -> u32 {
+        (self.stride * index as u64) as u32
+    }
+}
+
+/// Create bind group layout of object uniforms, bound with a dynamic
+/// offset per object.
+pub(crate) fn model_bind_group_layout(device: &Device) -> BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("model_bind_group_layout"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: true,
+                min_binding_size: wgpu::BufferSize::new(
+                    std::mem::size_of::<ObjectUniform>() as u64
+                ),
+            },
+            count: None,
+        }],
+    })
+}
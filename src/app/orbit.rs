@@ -0,0 +1,141 @@
+use sdl2::mouse::MouseButton;
+
+use super::cubemap::{look_at, mul};
+use super::input::InputState;
+
+/// Radian turned per pixel of mouse drag.
+const ROTATE_PER_PIXEL: f32 = 0.01;
+/// Pan per pixel of mouse drag, in `distance` units.
+const PAN_PER_PIXEL: f32 = 0.002;
+/// Distance multiplier per wheel step, scrolling away zoom in.
+const ZOOM_PER_STEP: f32 = 0.9;
+/// Pitch stop short of straight up and down, where the up vector would be
+/// parallel to the view direction.
+const MAX_PITCH: f32 = std::f32::consts::FRAC_PI_2 - 0.01;
+const MIN_DISTANCE: f32 = 0.01;
+const MAX_DISTANCE: f32 = 1000.0;
+
+/// Perspective camera turning around `target`, for inspecting 3D meshes.
+///
+/// Left drag rotates, the wheel zooms and middle drag pans, see `update`.
+/// Set with `XApp::set_orbit_camera`, it then replace `Camera2D` in the
+/// camera uniform. At zero `yaw` and `pitch` the camera looks toward `+z`
+/// like the perspective `Camera2D`.
+#[derive(Copy, Clone, Debug)]
+pub struct OrbitCamera {
+    /// Point looked at and turned around.
+    pub target: [f32; 3],
+    /// Distance from the camera to `target`.
+    pub distance: f32,
+    /// Angle around the `y` axis in radian, positive turns the camera
+    /// toward `+x`.
+    pub yaw: f32,
+    /// Angle above the `xz` plane in radian.
+    pub pitch: f32,
+    /// Yaw speed in radian per second while the scene isn't dragged, `0.0`
+    /// to stay still.
+    pub auto_rotate: f32,
+    /// Vertical field of view in radian.
+    pub fov_y: f32,
+    /// Distance of the near and far clip planes from the camera.
+    pub near: f32,
+    pub far: f32,
+}
+
+impl Default for OrbitCamera {
+    /// Looking at the origin from 3 units away with a 60 degree field of
+    /// view, clip planes at 0.1 and 100.0.
+    fn default() -> Self {
+        OrbitCamera {
+            target: [0.0; 3],
+            distance: 3.0,
+            yaw: 0.0,
+            pitch: 0.0,
+            auto_rotate: 0.0,
+            fov_y: std::f32::consts::FRAC_PI_3,
+            near: 0.1,
+            far: 100.0,
+        }
+    }
+}
+
+impl OrbitCamera {
+    pub fn new(target: [f32; 3], distance: f32) -> Self {
+        OrbitCamera {
+            target,
+            distance,
+            ..Default::default()
+        }
+    }
+
+    /// Apply mouse input of this frame, `dt` seconds long. Called by
+    /// `XApp::run` once per frame when the camera is set with
+    /// `XApp::set_orbit_camera`.
+    pub fn update(&mut self, input: &InputState, dt: f32) {
+        let (dx, dy) = input.mouse_delta();
+        let (dx, dy) = (dx as f32, dy as f32);
+        if input.is_mouse_button_down(MouseButton::Left) {
+            self.yaw -= dx * ROTATE_PER_PIXEL;
+            self.pitch = (self.pitch + dy * ROTATE_PER_PIXEL).clamp(-MAX_PITCH, MAX_PITCH);
+        } else {
+            self.yaw += self.auto_rotate * dt;
+        }
+
+        let wheel = input.wheel();
+        if wheel != 0.0 {
+            self.distance =
+                (self.distance * ZOOM_PER_STEP.powf(wheel)).clamp(MIN_DISTANCE, MAX_DISTANCE);
+        }
+
+        if input.is_mouse_button_down(MouseButton::Middle) {
+            // pan faster when far away, so the scene keeps up with the
+            // cursor
+            let [x, y, _] = self.axes();
+            let scale = self.distance * PAN_PER_PIXEL;
+            for (i, t) in self.target.iter_mut().enumerate() {
+                *t += (-dx * x[i] + dy * y[i]) * scale;
+            }
+        }
+    }
+
+    /// Camera position.
+    pub fn eye(&self) -> [f32; 3] {
+        let [_, _, forward] = self.axes();
+        [
+            self.target[0] - forward[0] * self.distance,
+            self.target[1] - forward[1] * self.distance,
+            self.target[2] - forward[2] * self.distance,
+        ]
+    }
+
+    /// View matrix, column major.
+    pub fn view(&self) -> [[f32; 4]; 4] {
+        let [_, _, forward] = self.axes();
+        look_at(self.eye(), forward, [0.0, 1.0, 0.0])
+    }
+
+    /// View projection matrix for a viewport of `aspect` width over
+    /// height, column major. Depth `near..far` is mapped to wgpu clip
+    /// range `0.0..1.0`.
+    pub fn view_proj(&self, aspect: f32) -> [[f32; 4]; 4] {
+        let f = 1.0 / (self.fov_y * 0.5).tan();
+        let depth = self.far / (self.far - self.near);
+        let proj = [
+            [f / aspect, 0.0, 0.0, 0.0],
+            [0.0, f, 0.0, 0.0],
+            [0.0, 0.0, depth, 1.0],
+            [0.0, 0.0, -self.near * depth, 0.0],
+        ];
+        mul(proj, self.view())
+    }
+
+    /// Right, up and forward unit vectors of the camera.
+    fn axes(&self) -> [[f32; 3]; 3] {
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+        let forward = [-cos_pitch * sin_yaw, -sin_pitch, cos_pitch * cos_yaw];
+        let right = [cos_yaw, 0.0, sin_yaw];
+        let up = [-sin_pitch * sin_yaw, cos_pitch, sin_pitch * cos_yaw];
+        [right, up, forward]
+    }
+}
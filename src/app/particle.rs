@@ -0,0 +1,255 @@
+use wgpu::{
+    BindGroup, BindGroupLayout, Buffer, Device, Queue, RenderPass, RenderPipeline, TextureFormat,
+};
+
+use super::validate::assert_draw_range;
+
+/// Particles alive at the same time, emission stop when reached.
+pub const MAX_PARTICLES: usize = 4096;
+
+/// Per-particle instance data, matches `ParticleInput` in `particle.wgsl`.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct ParticleInstance {
+    position: [f32; 2],
+    size: f32,
+    color: [f32; 4],
+}
+
+impl ParticleInstance {
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<ParticleInstance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
+
+/// One particle in world space.
+#[derive(Copy, Clone, Debug)]
+pub struct Particle {
+    pub position: [f32; 2],
+    /// World units per second.
+    pub velocity: [f32; 2],
+    /// Seconds left before the particle is recycled.
+    pub life: f32,
+    pub color: [f32; 4],
+}
+
+/// How new particles are emitted and moved.
+#[derive(Copy, Clone, Debug)]
+pub struct ParticleSettings {
+    /// Seconds a particle live, it fade out over that time.
+    pub lifetime: f32,
+    /// Minimum and maximum speed, direction is random.
+    pub speed: [f32; 2],
+    /// Acceleration in world units per second squared.
+    pub gravity: [f32; 2],
+    /// World width of a particle.
+    pub size: f32,
+    pub color: [f32; 4],
+}
+
+impl Default for ParticleSettings {
+    fn default() -> Self {
+        ParticleSettings {
+            lifetime: 1.0,
+            speed: [0.2, 0.6],
+            gravity: [0.0, -0.5],
+            size: 0.03,
+            color: [1.0, 0.7, 0.2, 1.0],
+        }
+    }
+}
+
+/// Particles simulated on the CPU and drawn as one instanced draw.
+///
+/// Alive particles are kept packed at the start of the list, a dead one is
+/// swapped with the last alive one so its slot is reused by `emit`.
+pub struct ParticleSystem {
+    pipeline: RenderPipeline,
+    instance_buffer: Buffer,
+    particles: Vec<Particle>,
+    /// Number of uploaded instances, drawn until the next upload.
+    drawn: u32,
+    settings: ParticleSettings,
+    seed: u32,
+}
+
+impl ParticleSystem {
+    pub(crate) fn new(
+        device: &Device,
+        camera_layout: &BindGroupLayout,
+        format: TextureFormat,
+        depth_stencil: wgpu::DepthStencilState,
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::include_wgsl!("particle.wgsl"));
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("particle_pipeline_layout"),
+            bind_group_layouts: &[camera_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("particle_pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                buffers: &[ParticleInstance::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: Some(depth_stencil),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+        // sized for every particle once, it never grow
+        let instance_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("particle instance buffer"),
+            size: (MAX_PARTICLES * std::mem::size_of::<ParticleInstance>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        ParticleSystem {
+            pipeline,
+            instance_buffer,
+            particles: Vec::with_capacity(MAX_PARTICLES),
+            drawn: 0,
+            settings: ParticleSettings::default(),
+            seed: 0x9e37_79b9,
+        }
+    }
+
+    pub fn settings(&self) -> &ParticleSettings {
+        &self.settings
+    }
+
+    /// Used for particles emitted from now on.
+    pub fn set_settings(&mut self, settings: ParticleSettings) {
+        self.settings = settings;
+    }
+
+    /// Alive particles.
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+
+    /// Spawn up to `count` particles at `origin` going in random
+    /// directions. Particles over `MAX_PARTICLES` are not emitted.
+    pub fn emit(&mut self, count: usize, origin: [f32; 2]) {
+        let count = count.min(MAX_PARTICLES - self.particles.len());
+        for _ in 0..count {
+            let angle = self.random() * std::f32::consts::TAU;
+            let [min, max] = self.settings.speed;
+            let speed = min + (max - min) * self.random();
+            self.particles.push(Particle {
+                position: origin,
+                velocity: [angle.cos() * speed, angle.sin() * speed],
+                life: self.settings.lifetime,
+                color: self.settings.color,
+            });
+        }
+    }
+
+    /// Move particles by `dt` seconds and recycle the dead ones.
+    pub fn update(&mut self, dt: f32) {
+        let gravity = self.settings.gravity;
+        let mut i = 0;
+        while i < self.particles.len() {
+            let particle = &mut self.particles[i];
+            particle.life -= dt;
+            if particle.life <= 0.0 {
+                self.particles.swap_remove(i);
+                continue;
+            }
+            let Particle {
+                position, velocity, ..
+            } = particle;
+            for ((p, v), g) in position.iter_mut().zip(velocity).zip(gravity) {
+                *v += g * dt;
+                *p += *v * dt;
+            }
+            i += 1;
+        }
+    }
+
+    /// Remove every particle.
+    pub fn clear(&mut self) {
+        self.particles.clear();
+    }
+
+    /// Write alive particles to the instance buffer, faded by their life.
+    pub(crate) fn upload(&mut self, queue: &Queue) {
+        let lifetime = self.settings.lifetime.max(f32::EPSILON);
+        let instances = self
+            .particles
+            .iter()
+            .map(|p| {
+                let mut color = p.color;
+                color[3] *= (p.life / lifetime).min(1.0);
+                ParticleInstance {
+                    position: p.position,
+                    size: self.settings.size,
+                    color,
+                }
+            })
+            .collect::<Vec<_>>();
+        if !instances.is_empty() {
+            queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&instances));
+        }
+        self.drawn = instances.len() as u32;
+    }
+
+    pub(crate) fn draw(&self, render_pass: &mut RenderPass, camera_bind_group: &BindGroup) {
+        if self.drawn == 0 {
+            return;
+        }
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, camera_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.instance_buffer.slice(..));
+        assert_draw_range(
+            "Particle instance",
+            &(0..self.drawn),
+            &self.instance_buffer,
+            std::mem::size_of::<ParticleInstance>(),
+        );
+        render_pass.draw(0..6, 0..self.drawn);
+    }
+
+    /// Xorshift random number in `0.0..1.0`.
+    fn random(&mut self) -> f32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        (self.seed >> 8) as f32 / (1 << 24) as f32
+    }
+}
@@ -0,0 +1,50 @@
+// Round particles drawn as camera facing quads, one instance per particle.
+
+@group(0) @binding(0)
+var<uniform> camera: mat4x4<f32>;
+
+struct ParticleInput {
+    // world position of the center
+    @location(0) position: vec2<f32>,
+    // world width and height
+    @location(1) size: f32,
+    @location(2) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // position inside the quad, -1.0..1.0 on both axes
+    @location(0) local: vec2<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) in_vertex_index: u32,
+    particle: ParticleInput,
+) -> VertexOutput {
+    // two counter clockwise triangles of quad centered on the particle
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, 1.0),
+    );
+    let corner = corners[in_vertex_index];
+    let position = particle.position + corner * particle.size * 0.5;
+
+    var out: VertexOutput;
+    out.clip_position = camera * vec4<f32>(position, 0.0, 1.0);
+    out.local = corner;
+    out.color = particle.color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // soft edged disc
+    let fade = 1.0 - smoothstep(0.6, 1.0, length(in.local));
+    return vec4<f32>(in.color.rgb, in.color.a * fade);
+}
@@ -0,0 +1,156 @@
+use wgpu::{BindGroup, Color, CommandEncoder, Device, LoadOp, Queue, TextureFormat, TextureView};
+
+/// Order of the built-in passes, see `XApp::add_pass`. Passes with a
+/// lower order are recorded first.
+pub const COMPUTE_PASS_ORDER: i32 = 100;
+pub const SCENE_PASS_ORDER: i32 = 200;
+pub const POST_PASS_ORDER: i32 = 300;
+pub const TEXT_PASS_ORDER: i32 = 400;
+
+/// Color used to clear the scene by default.
+pub const DEFAULT_CLEAR_COLOR: Color = Color {
+    r: 0.1,
+    g: 0.2,
+    b: 0.3,
+    a: 1.0,
+};
+
+/// How the scene pass start, see `XApp::set_scene_pass`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScenePass {
+    /// `Clear` start from a solid color, `Load` draw over what the target
+    /// already hold.
+    pub load: LoadOp<Color>,
+}
+
+impl ScenePass {
+    pub fn clear(color: Color) -> Self {
+        ScenePass {
+            load: LoadOp::Clear(color),
+        }
+    }
+
+    /// Keep previous content of the target. Swapchain textures content is
+    /// undefined after present, so it is only useful on headless app and
+    /// with post process, whose scene texture is kept between frames.
+    pub fn load() -> Self {
+        ScenePass { load: LoadOp::Load }
+    }
+}
+
+impl Default for ScenePass {
+    fn default() -> Self {
+        Self::clear(DEFAULT_CLEAR_COLOR)
+    }
+}
+
+/// Pass added to the frame with `XApp::add_pass`.
+pub trait FramePass {
+    /// Record the pass of this frame into `context.encoder`.
+    fn record(&mut self, context: &mut PassContext);
+}
+
+/// What a `FramePass` can record with, valid for one frame.
+pub struct PassContext<'a> {
+    pub device: &'a Device,
+    pub queue: &'a Queue,
+    /// Encoder of this pass only, submitted with the other passes in
+    /// order.
+    pub encoder: &'a mut CommandEncoder,
+    /// Target of the scene pass, the offscreen scene texture when post
+    /// process is enabled, otherwise `view`.
+    pub scene_view: &'a TextureView,
+    /// Depth stencil attachment of the scene pass.
+    pub depth_stencil_view: &'a TextureView,
+    /// Swapchain or offscreen texture presented this frame.
+    pub view: &'a TextureView,
+    /// Format of `view`.
+    pub format: TextureFormat,
+    /// Camera uniform of this frame, as bound at group 1 of the scene
+    /// pipelines.
+    pub camera_bind_group: &'a BindGroup,
+    /// Size of `view` in pixels.
+    pub width: u32,
+    pub height: u32,
+}
+
+/// What a registered pass records.
+pub(crate) enum PassKind {
+    Compute,
+    Scene,
+    Post,
+    Text,
+    Custom(Box<dyn FramePass>),
+}
+
+pub(crate) struct NamedPass {
+    pub(crate) name: String,
+    pub(crate) order: i32,
+    pub(crate) kind: PassKind,
+}
+
+/// Passes of a frame sorted by order, those of equal order in the order
+/// they were added.
+pub(crate) struct PassList {
+    passes: Vec<NamedPass>,
+}
+
+impl PassList {
+    /// The built-in passes: `compute`, `scene`, `post` and `text`.
+    pub(crate) fn new() -> Self {
+        let builtin = |name: &str, order, kind| NamedPass {
+            name: name.to_string(),
+            order,
+            kind,
+        };
+        PassList {
+            passes: vec![
+                builtin("compute", COMPUTE_PASS_ORDER, PassKind::Compute),
+                builtin("scene", SCENE_PASS_ORDER, PassKind::Scene),
+                builtin("post", POST_PASS_ORDER, PassKind::Post),
+                builtin("text", TEXT_PASS_ORDER, PassKind::Text),
+            ],
+        }
+    }
+
+    pub(crate) fn add(
+        &mut self,
+        name: &str,
+        order: i32,
+        pass: Box<dyn FramePass>,
+    ) -> Result<(), String> {
+        if self.passes.iter().any(|x| x.name == name) {
+            return Err(format!("Pass {} already exist", name));
+        }
+        let index = self.passes.partition_point(|x| x.order <= order);
+        self.passes.insert(
+            index,
+            NamedPass {
+                name: name.to_string(),
+                order,
+                kind: PassKind::Custom(pass),
+            },
+        );
+        Ok(())
+    }
+
+    /// Remove added pass `name`, built-in passes stay.
+    pub(crate) fn remove(&mut self, name: &str) -> Option<Box<dyn FramePass>> {
+        let index = self
+            .passes
+            .iter()
+            .position(|x| x.name == name && matches!(x.kind, PassKind::Custom(_)))?;
+        match self.passes.remove(index).kind {
+            PassKind::Custom(pass) => Some(pass),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn names(&self) -> Vec<String> {
+        self.passes.iter().map(|x| x.name.clone()).collect()
+    }
+
+    pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = &mut NamedPass> {
+        self.passes.iter_mut()
+    }
+}
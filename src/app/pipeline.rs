@@ -0,0 +1,187 @@
+use std::borrow::Cow;
+
+use wgpu::{
+    BlendState, ColorTargetState, ColorWrites, DepthStencilState, Device, Face, FrontFace,
+    PipelineCompilationOptions, PipelineLayout, PolygonMode, PrimitiveState, PrimitiveTopology,
+    RenderPipeline, RenderPipelineDescriptor, ShaderModule, StencilState, TextureFormat,
+};
+
+use super::{InstanceRaw, Vertex};
+
+/// Format of the depth stencil attachment of the scene pass.
+pub(crate) const DEPTH_STENCIL_FORMAT: TextureFormat = TextureFormat::Depth24PlusStencil8;
+
+/// Size of per-draw push constant data: a `vec4<f32>` tint.
+pub(crate) const PUSH_CONSTANT_SIZE: u32 = std::mem::size_of::<[f32; 4]>() as u32;
+
+const DRAW_DATA_PUSH_CONSTANT: &str = r#"
+struct DrawData {
+    tint: vec4<f32>,
+};
+
+var<push_constant> draw: DrawData;
+
+fn draw_tint() -> vec4<f32> {
+    return draw.tint;
+}
+"#;
+
+const DRAW_DATA_UNIFORM: &str = r#"
+fn draw_tint() -> vec4<f32> {
+    return object.tint;
+}
+"#;
+
+/// Compile scene shader. Per-draw data is read from push constant when
+/// `push_constants` is set, otherwise from the object uniform.
+pub(crate) fn create_scene_shader(device: &Device, push_constants: bool) -> ShaderModule {
+    let draw_data = if push_constants {
+        DRAW_DATA_PUSH_CONSTANT
+    } else {
+        DRAW_DATA_UNIFORM
+    };
+    let source = format!("{}\n{}", include_str!("shader.wgsl"), draw_data);
+    device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("shader.wgsl"),
+        source: wgpu::ShaderSource::Wgsl(Cow::Owned(source)),
+    })
+}
+
+/// Color target writing every channel of `format`. Use
+/// `BlendState::REPLACE` for opaque and `BlendState::ALPHA_BLENDING` for
+/// transparent geometry.
+pub(crate) fn color_target(format: TextureFormat, blend: BlendState) -> ColorTargetState {
+    ColorTargetState {
+        format,
+        blend: Some(blend),
+        write_mask: ColorWrites::ALL,
+    }
+}
+
+/// Depth stencil state of pipelines drawn in the scene pass. Depth is not
+/// tested, geometry is drawn in submission order, only `stencil` apply.
+pub(crate) fn scene_depth_stencil(stencil: StencilState) -> DepthStencilState {
+    DepthStencilState {
+        format: DEPTH_STENCIL_FORMAT,
+        depth_write_enabled: false,
+        depth_compare: wgpu::CompareFunction::Always,
+        stencil,
+        bias: wgpu::DepthBiasState::default(),
+    }
+}
+
+/// Depth stencil state of opaque objects in the depth prepass: nearest
+/// fragment win and its depth is written.
+pub(crate) fn prepass_depth_stencil(stencil: StencilState) -> DepthStencilState {
+    DepthStencilState {
+        depth_write_enabled: true,
+        depth_compare: wgpu::CompareFunction::Less,
+        ..scene_depth_stencil(stencil)
+    }
+}
+
+/// Depth stencil state of opaque objects in the color pass after the
+/// depth prepass. Only the fragment that won the prepass is shaded, the
+/// stencil was already tested there.
+pub(crate) fn equal_depth_stencil() -> DepthStencilState {
+    DepthStencilState {
+        depth_compare: wgpu::CompareFunction::Equal,
+        ..scene_depth_stencil(StencilState::default())
+    }
+}
+
+/// Pipelines of the depth prepass, see `XAppBuilder::depth_prepass`.
+pub(crate) struct DepthPrepass {
+    /// Stencil masks, written again in the prepass for the opaque
+    /// stencil test.
+    pub(crate) mask: RenderPipeline,
+    /// Opaque objects, depth only.
+    pub(crate) depth: RenderPipeline,
+    /// Opaque objects in the color pass.
+    pub(crate) shade: RenderPipeline,
+}
+
+/// Filled triangle list of scene objects. Triangles whose vertices go
+/// `front_face` on screen are front facing, `cull_mode` faces are skipped.
+pub(crate) fn scene_primitive(front_face: FrontFace, cull_mode: Option<Face>) -> PrimitiveState {
+    PrimitiveState {
+        topology: PrimitiveTopology::TriangleList,
+        strip_index_format: None,
+        front_face,
+        cull_mode,
+        unclipped_depth: false,
+        polygon_mode: PolygonMode::Fill,
+        conservative: false,
+    }
+}
+
+/// Create render pipeline drawing `Vertex` + `InstanceRaw` buffers with
+/// `vs_main`/`fs_main` entry points of `shader` into `color_target`.
+/// `PolygonMode::Line` needs the `POLYGON_MODE_LINE` feature.
+pub(crate) fn create_pipeline(
+    device: &Device,
+    layout: &PipelineLayout,
+    shader: &ShaderModule,
+    color_target: ColorTargetState,
+    sample_count: u32,
+    primitive: PrimitiveState,
+    depth_stencil: DepthStencilState,
+) -> RenderPipeline {
+    let color_target = [Some(color_target)];
+    let pipeline_desc = RenderPipelineDescriptor {
+        label: Some("render_pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some("vs_main"),
+            compilation_options: PipelineCompilationOptions::default(),
+            buffers: &[Vertex::desc(), InstanceRaw::desc()],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some("fs_main"),
+            compilation_options: PipelineCompilationOptions::default(),
+            targets: &color_target,
+        }),
+        primitive,
+        depth_stencil: Some(depth_stencil),
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+
+        multiview: None,
+        cache: None,
+    };
+
+    device.create_render_pipeline(&pipeline_desc)
+}
+
+/// Create render pipeline with the vertex stage of `create_pipeline` and
+/// no fragment stage, for a pass without color attachment. Only depth and
+/// stencil are written.
+pub(crate) fn create_depth_pipeline(
+    device: &Device,
+    layout: &PipelineLayout,
+    shader: &ShaderModule,
+    primitive: PrimitiveState,
+    depth_stencil: DepthStencilState,
+) -> RenderPipeline {
+    device.create_render_pipeline(&RenderPipelineDescriptor {
+        label: Some("depth_prepass_pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some("vs_main"),
+            compilation_options: PipelineCompilationOptions::default(),
+            buffers: &[Vertex::desc(), InstanceRaw::desc()],
+        },
+        fragment: None,
+        primitive,
+        depth_stencil: Some(depth_stencil),
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    })
+}
@@ -0,0 +1,188 @@
+use std::sync::Arc;
+
+use wgpu::{
+    BindGroup, BindGroupLayout, CommandEncoder, Device, RenderPipeline, Sampler, TextureFormat,
+    TextureView,
+};
+
+/// Fullscreen triangle `vs_main` and plain copy `fs_main`, also used by
+/// the blitter.
+pub(crate) const POST_SHADER: &str = include_str!("post.wgsl");
+
+/// Color texture that can be rendered to and then sampled or copied.
+pub struct OffscreenTarget {
+    texture: wgpu::Texture,
+    view: TextureView,
+}
+
+impl OffscreenTarget {
+    pub fn new(device: &Device, width: u32, height: u32, format: TextureFormat) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("offscreen color target"),
+            size: wgpu::Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        OffscreenTarget { texture, view }
+    }
+
+    pub fn texture(&self) -> &wgpu::Texture {
+        &self.texture
+    }
+
+    pub fn view(&self) -> &TextureView {
+        &self.view
+    }
+
+    pub fn format(&self) -> TextureFormat {
+        self.texture.format()
+    }
+}
+
+/// Scene is first drawn into `scene`, then a final pass sample it to the
+/// output. Effects like bloom go in the final pass shader.
+pub(crate) struct PostProcess {
+    pipeline: RenderPipeline,
+    layout: Arc<BindGroupLayout>,
+    sampler: Sampler,
+    scene: OffscreenTarget,
+    bind_group: BindGroup,
+}
+
+impl PostProcess {
+    /// With `fxaa` the final pass run `fs_fxaa` from fxaa.wgsl instead of
+    /// plain copy.
+    pub(crate) fn new(
+        device: &Device,
+        layout: Arc<BindGroupLayout>,
+        width: u32,
+        height: u32,
+        format: TextureFormat,
+        fxaa: bool,
+    ) -> Self {
+        let (source, entry_point) = if fxaa {
+            (
+                format!("{}\n{}", POST_SHADER, include_str!("fxaa.wgsl")),
+                "fs_fxaa",
+            )
+        } else {
+            (POST_SHADER.to_string(), "fs_main")
+        };
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("post.wgsl"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("post_pipeline_layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("post_pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some(entry_point),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("post sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let scene = OffscreenTarget::new(device, width, height, format);
+        let bind_group = Self::create_bind_group(device, &layout, &sampler, &scene);
+
+        PostProcess {
+            pipeline,
+            layout,
+            sampler,
+            scene,
+            bind_group,
+        }
+    }
+
+    fn create_bind_group(
+        device: &Device,
+        layout: &BindGroupLayout,
+        sampler: &Sampler,
+        scene: &OffscreenTarget,
+    ) -> BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("post bind group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(scene.view()),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+        })
+    }
+
+    /// Recreate scene texture for new output size.
+    pub(crate) fn resize(&mut self, device: &Device, width: u32, height: u32) {
+        self.scene = OffscreenTarget::new(device, width, height, self.scene.format());
+        self.bind_group = Self::create_bind_group(device, &self.layout, &self.sampler, &self.scene);
+    }
+
+    /// View the scene pass should draw into.
+    pub(crate) fn scene_view(&self) -> &TextureView {
+        self.scene.view()
+    }
+
+    /// Record final pass sampling the scene into `output`.
+    pub(crate) fn draw(&self, encoder: &mut CommandEncoder, output: &TextureView) {
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Post Process Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: output,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+}
@@ -27,23 +27,80 @@
 
 // Vertex shader
 
+// `draw_tint()` is appended at runtime, reading either push constant or
+// `object.tint` depending on device features.
+
+struct ObjectUniform {
+    model: mat4x4<f32>,
+    tint: vec4<f32>,
+    opacity: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> object: ObjectUniform;
+
+// view projection of `Camera2D`, it scales x by the window aspect ratio so
+// the scene isn't stretched on non square window
+@group(1) @binding(0)
+var<uniform> camera: mat4x4<f32>;
+
+// x: 0 = interpolated vertex color, 1 = flat color, see
+// `XApp::set_render_mode`
+// y: 1 when the target format is linear and output must be gamma encoded
+// by the shader, sRGB formats are encoded by the hardware
+@group(1) @binding(1)
+var<uniform> render_mode: vec4<u32>;
+
+struct Light {
+    // direction the light travel to, normalized
+    direction: vec3<f32>,
+    color: vec3<f32>,
+    ambient: f32,
+};
+
+@group(2) @binding(0)
+var<uniform> light: Light;
+
 struct VertexInput {
     @location(0) position: vec3<f32>,
     @location(1) color: vec3<f32>,
+    @location(2) normal: vec3<f32>,
+};
+
+struct InstanceInput {
+    @location(5) model_0: vec4<f32>,
+    @location(6) model_1: vec4<f32>,
+    @location(7) model_2: vec4<f32>,
+    @location(8) model_3: vec4<f32>,
 };
 
 struct VertexOutput {
-    @builtin(position) clip_position: vec4<f32>,
+    // same depth in the depth prepass and the color pass, which compare
+    // it with `Equal`
+    @builtin(position) @invariant clip_position: vec4<f32>,
     @location(0) color: vec3<f32>,
+    @location(1) @interpolate(flat) flat_color: vec3<f32>,
+    @location(2) normal: vec3<f32>,
 };
 
 @vertex
 fn vs_main(
     model: VertexInput,
+    instance: InstanceInput,
 ) -> VertexOutput {
+    let instance_matrix = mat4x4<f32>(
+        instance.model_0,
+        instance.model_1,
+        instance.model_2,
+        instance.model_3,
+    );
+
     var out: VertexOutput;
     out.color = model.color;
-    out.clip_position = vec4<f32>(model.position, 1.0);
+    out.flat_color = model.color;
+    // model matrices only rotate and scale uniformly, no inverse transpose
+    out.normal = (object.model * instance_matrix * vec4<f32>(model.normal, 0.0)).xyz;
+    out.clip_position = camera * object.model * instance_matrix * vec4<f32>(model.position, 1.0);
     return out;
 }
 
@@ -51,5 +108,15 @@ fn vs_main(
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return vec4<f32>(in.color, 1.0);
+    let tint = draw_tint();
+    var color = in.color;
+    if render_mode.x == 1u {
+        color = in.flat_color;
+    }
+    let diffuse = max(dot(normalize(in.normal), -light.direction), 0.0);
+    color = color * (light.color * diffuse + vec3<f32>(light.ambient)) * tint.rgb;
+    if render_mode.y == 1u {
+        color = pow(max(color, vec3<f32>(0.0)), vec3<f32>(1.0 / 2.2));
+    }
+    return vec4<f32>(color, object.opacity * tint.a);
 }
@@ -0,0 +1,39 @@
+// Skybox: cubemap sampled along the view direction of each pixel, drawn
+// on the far plane behind the scene.
+
+// inverse of the camera view projection
+@group(0) @binding(0)
+var<uniform> inv_view_proj: mat4x4<f32>;
+
+@group(0) @binding(1)
+var sky_texture: texture_cube<f32>;
+
+@group(0) @binding(2)
+var sky_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) ndc: vec2<f32>,
+};
+
+// one triangle covering the screen at depth 1.0
+@vertex
+fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((in_vertex_index << 1u) & 2u), f32(in_vertex_index & 2u));
+    let ndc = uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0);
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(ndc, 1.0, 1.0);
+    out.ndc = ndc;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // direction from the near to the far point under the pixel, the
+    // camera translation cancels out
+    let near = inv_view_proj * vec4<f32>(in.ndc, 0.0, 1.0);
+    let far = inv_view_proj * vec4<f32>(in.ndc, 1.0, 1.0);
+    let direction = far.xyz / far.w - near.xyz / near.w;
+    return textureSample(sky_texture, sky_sampler, direction);
+}
@@ -0,0 +1,190 @@
+use std::ops::Range;
+
+use wgpu::{
+    BindGroup, BindGroupLayout, Buffer, Device, Queue, RenderPass, RenderPipeline, TextureFormat,
+};
+
+use super::texture::Texture;
+use super::validate::assert_draw_range;
+
+/// Per-sprite instance data, matches `SpriteInput` in `sprite.wgsl`.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct SpriteInstance {
+    dest: [f32; 4],
+    src: [f32; 4],
+    tint: [f32; 4],
+}
+
+impl SpriteInstance {
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<SpriteInstance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
+
+/// Collect textured quads between `begin` and `end`, then draw them with
+/// one instanced draw per texture.
+pub(crate) struct SpriteBatch {
+    pipeline: RenderPipeline,
+    instance_buffer: Option<Buffer>,
+    pending: Vec<(usize, SpriteInstance)>,
+    // texture id and instance range of the last `end`
+    draws: Vec<(usize, Range<u32>)>,
+}
+
+impl SpriteBatch {
+    pub(crate) fn new(
+        device: &Device,
+        camera_layout: &BindGroupLayout,
+        texture_layout: &BindGroupLayout,
+        format: TextureFormat,
+        depth_stencil: wgpu::DepthStencilState,
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::include_wgsl!("sprite.wgsl"));
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("sprite_pipeline_layout"),
+            bind_group_layouts: &[camera_layout, texture_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("sprite_pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                buffers: &[SpriteInstance::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: Some(depth_stencil),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        SpriteBatch {
+            pipeline,
+            instance_buffer: None,
+            pending: Vec::new(),
+            draws: Vec::new(),
+        }
+    }
+
+    /// Start a new batch, sprites of the previous one stay drawn until `end`.
+    pub(crate) fn begin(&mut self) {
+        self.pending.clear();
+    }
+
+    pub(crate) fn draw_sprite(
+        &mut self,
+        texture_id: usize,
+        dest_rect: [f32; 4],
+        src_rect: [f32; 4],
+        tint: [f32; 4],
+    ) {
+        self.pending.push((
+            texture_id,
+            SpriteInstance {
+                dest: dest_rect,
+                src: src_rect,
+                tint,
+            },
+        ));
+    }
+
+    /// Sort sprites by texture and upload them, replacing the drawn batch.
+    pub(crate) fn end(&mut self, device: &Device, queue: &Queue) {
+        // stable sort keep submission order for sprites sharing a texture
+        self.pending.sort_by_key(|(texture_id, _)| *texture_id);
+
+        self.draws.clear();
+        for (i, (texture_id, _)) in self.pending.iter().enumerate() {
+            let i = i as u32;
+            match self.draws.last_mut() {
+                Some((id, range)) if id == texture_id => range.end = i + 1,
+                _ => self.draws.push((*texture_id, i..i + 1)),
+            }
+        }
+
+        let instances = self.pending.iter().map(|(_, x)| *x).collect::<Vec<_>>();
+        let data: &[u8] = bytemuck::cast_slice(&instances);
+        if data.is_empty() {
+            return;
+        }
+        match &self.instance_buffer {
+            Some(buffer) if buffer.size() >= data.len() as wgpu::BufferAddress => {
+                queue.write_buffer(buffer, 0, data)
+            }
+            _ => {
+                self.instance_buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("sprite instance buffer"),
+                    size: data.len().next_power_of_two() as wgpu::BufferAddress,
+                    usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                }));
+                if let Some(buffer) = &self.instance_buffer {
+                    queue.write_buffer(buffer, 0, data);
+                }
+            }
+        }
+    }
+
+    /// Record draws of the last ended batch. Sprites with unknown texture
+    /// id are skipped.
+    pub(crate) fn draw(
+        &self,
+        render_pass: &mut RenderPass,
+        camera_bind_group: &BindGroup,
+        textures: &[Texture],
+    ) {
+        let buffer = match &self.instance_buffer {
+            Some(x) if !self.draws.is_empty() => x,
+            _ => return,
+        };
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, camera_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, buffer.slice(..));
+        for (texture_id, range) in &self.draws {
+            if let Some(texture) = textures.get(*texture_id) {
+                render_pass.set_bind_group(1, texture.bind_group(), &[]);
+                assert_draw_range(
+                    "Sprite instance",
+                    range,
+                    buffer,
+                    std::mem::size_of::<SpriteInstance>(),
+                );
+                render_pass.draw(0..6, range.clone());
+            }
+        }
+    }
+}
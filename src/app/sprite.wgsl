@@ -0,0 +1,54 @@
+// Textured quads drawn with one instance per sprite.
+
+@group(0) @binding(0)
+var<uniform> camera: mat4x4<f32>;
+
+@group(1) @binding(0)
+var sprite_texture: texture_2d<f32>;
+
+@group(1) @binding(1)
+var sprite_sampler: sampler;
+
+struct SpriteInput {
+    // world rectangle: bottom left corner xy, size zw
+    @location(0) dest: vec4<f32>,
+    // texture rectangle in uv: top left corner xy, size zw
+    @location(1) src: vec4<f32>,
+    @location(2) tint: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) tint: vec4<f32>,
+};
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) in_vertex_index: u32,
+    sprite: SpriteInput,
+) -> VertexOutput {
+    // two counter clockwise triangles of unit quad
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+    );
+    let corner = corners[in_vertex_index];
+    let position = sprite.dest.xy + corner * sprite.dest.zw;
+
+    var out: VertexOutput;
+    out.clip_position = camera * vec4<f32>(position, 0.0, 1.0);
+    // texture v grows downward while world y grows upward
+    out.uv = sprite.src.xy + vec2<f32>(corner.x, 1.0 - corner.y) * sprite.src.zw;
+    out.tint = sprite.tint;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(sprite_texture, sprite_sampler, in.uv) * in.tint;
+}
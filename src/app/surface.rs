@@ -0,0 +1,136 @@
+use log::warn;
+use sdl2::video::Window;
+use wgpu::{
+    Adapter, CompositeAlphaMode, Device, Instance, PresentMode, Surface, SurfaceCapabilities,
+    SurfaceConfiguration, SurfaceTargetUnsafe, TextureFormat,
+};
+
+/// What the main window surface support with the adapter, e.g. to list
+/// choices in a graphics settings menu. Empty for headless app.
+#[derive(Clone, Debug, Default)]
+pub struct SurfaceCapabilitiesSummary {
+    /// Supported formats, preferred first.
+    pub formats: Vec<TextureFormat>,
+    /// Supported present modes, see `XApp::set_present_mode`.
+    pub present_modes: Vec<PresentMode>,
+    pub alpha_modes: Vec<CompositeAlphaMode>,
+}
+
+impl From<SurfaceCapabilities> for SurfaceCapabilitiesSummary {
+    fn from(capabilities: SurfaceCapabilities) -> Self {
+        SurfaceCapabilitiesSummary {
+            formats: capabilities.formats,
+            present_modes: capabilities.present_modes,
+            alpha_modes: capabilities.alpha_modes,
+        }
+    }
+}
+
+/// Format, present mode and alpha mode the output is configured with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SurfaceSelection {
+    pub format: TextureFormat,
+    pub present_mode: PresentMode,
+    pub alpha_mode: CompositeAlphaMode,
+}
+
+/// Instance and adapter of a windowed app, kept to create the surface of
+/// windows added after build.
+pub(crate) struct SurfaceSource {
+    pub(crate) instance: Instance,
+    pub(crate) adapter: Adapter,
+}
+
+/// Window added with `XApp::add_window`, drawn with the device of the
+/// main window.
+pub(crate) struct WindowSurface<'l> {
+    // dropped before the window it draws to
+    surface: Surface<'l>,
+    config: SurfaceConfiguration,
+    /// Scene pass attachment sized for this window.
+    depth_stencil_view: wgpu::TextureView,
+    window: Window,
+}
+
+impl<'l> WindowSurface<'l> {
+    /// Surface of `window` configured like `main_config` but with the size
+    /// of the window. Fail when the adapter can't present to it in the
+    /// format the pipelines are built for.
+    pub(crate) fn new(
+        source: &SurfaceSource,
+        device: &Device,
+        window: Window,
+        main_config: &SurfaceConfiguration,
+        size: (u32, u32),
+    ) -> Result<Self, String> {
+        let surface = unsafe {
+            let target = SurfaceTargetUnsafe::from_window(&window).map_err(|e| e.to_string())?;
+            source
+                .instance
+                .create_surface_unsafe(target)
+                .map_err(|e| e.to_string())?
+        };
+        let capabilities = surface.get_capabilities(&source.adapter);
+        if !capabilities.formats.contains(&main_config.format) {
+            return Err(format!(
+                "Window surface doesn't support format {:?} of the main window",
+                main_config.format
+            ));
+        }
+        // same view format as the main window, drawn with its pipelines
+        let config = SurfaceConfiguration {
+            width: size.0.max(1),
+            height: size.1.max(1),
+            ..main_config.clone()
+        };
+        surface.configure(device, &config);
+        let depth_stencil_view =
+            super::create_depth_stencil_view(device, config.width, config.height);
+
+        Ok(WindowSurface {
+            surface,
+            config,
+            depth_stencil_view,
+            window,
+        })
+    }
+
+    /// SDL window id, as found in `Event::Window::window_id`.
+    pub(crate) fn id(&self) -> u32 {
+        self.window.id()
+    }
+
+    pub(crate) fn window(&self) -> &Window {
+        &self.window
+    }
+
+    pub(crate) fn size(&self) -> (u32, u32) {
+        (self.config.width, self.config.height)
+    }
+
+    pub(crate) fn depth_stencil_view(&self) -> &wgpu::TextureView {
+        &self.depth_stencil_view
+    }
+
+    pub(crate) fn resize(&mut self, device: &Device, width: u32, height: u32) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        self.config.width = width;
+        self.config.height = height;
+        self.surface.configure(device, &self.config);
+        self.depth_stencil_view = super::create_depth_stencil_view(device, width, height);
+    }
+
+    /// Next swapchain texture, `None` with a warning when the surface
+    /// can't give one this frame.
+    pub(crate) fn current_texture(&self) -> Option<wgpu::SurfaceTexture> {
+        match self.surface.get_current_texture() {
+            Ok(x) => Some(x),
+            Err(e) => {
+                warn!("Skip frame of window {}: {}", self.window.id(), e);
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,328 @@
+use std::collections::HashMap;
+
+use log::warn;
+use wgpu::{
+    util::DeviceExt, AddressMode, BindGroup, BindGroupLayout, BindGroupLayoutEntry, Device,
+    FilterMode, Queue, Sampler, TextureView,
+};
+
+/// Highest anisotropy wgpu accept.
+pub(crate) const MAX_ANISOTROPY: u16 = 16;
+
+/// How a texture is sampled. Default is linear filtering clamped to edge.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SamplerOptions {
+    /// Min and mag filter, `Nearest` keep pixel art crisp.
+    pub filter: FilterMode,
+    /// Address mode of u and v, `Repeat` tile the texture.
+    pub address_mode: AddressMode,
+    /// Samples taken along the slope of surfaces seen at grazing angles,
+    /// `1` to disable, clamped to `1..=16`. Above 1 the sampler filter
+    /// linearly between mip levels too, so it needs `Linear` filter and
+    /// is limited by `XAppBuilder::max_anisotropy`.
+    pub anisotropy: u16,
+    /// Generate mip levels on upload so minified textures don't shimmer.
+    /// Disable for pixel art, with `Nearest` filter, to keep every texel
+    /// crisp.
+    pub mipmaps: bool,
+}
+
+impl Default for SamplerOptions {
+    fn default() -> Self {
+        SamplerOptions {
+            filter: FilterMode::Linear,
+            address_mode: AddressMode::ClampToEdge,
+            anisotropy: 1,
+            mipmaps: true,
+        }
+    }
+}
+
+/// Samplers created so far, textures with the same options share one.
+pub(crate) struct SamplerCache {
+    samplers: HashMap<SamplerOptions, Sampler>,
+    /// Highest anisotropy of the device, 1 when it can't filter
+    /// anisotropically.
+    max_anisotropy: u16,
+}
+
+impl SamplerCache {
+    pub(crate) fn new(max_anisotropy: u16) -> Self {
+        SamplerCache {
+            samplers: HashMap::new(),
+            max_anisotropy: max_anisotropy.clamp(1, MAX_ANISOTROPY),
+        }
+    }
+
+    /// Sampler for `options`, created on first use.
+    pub(crate) fn get(&mut self, device: &Device, options: SamplerOptions) -> &Sampler {
+        let max_anisotropy = self.max_anisotropy;
+        self.samplers.entry(options).or_insert_with(|| {
+            let anisotropy = anisotropy(options, max_anisotropy);
+            // anisotropic filtering need every filter linear, trilinear
+            // is kept when the device doesn't support it
+            let trilinear = options.mipmaps || options.anisotropy > 1;
+            let mipmap_filter = if trilinear && options.filter == FilterMode::Linear {
+                FilterMode::Linear
+            } else {
+                FilterMode::Nearest
+            };
+            device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some("texture sampler"),
+                address_mode_u: options.address_mode,
+                address_mode_v: options.address_mode,
+                address_mode_w: options.address_mode,
+                mag_filter: options.filter,
+                min_filter: options.filter,
+                mipmap_filter,
+                anisotropy_clamp: anisotropy,
+                ..Default::default()
+            })
+        })
+    }
+}
+
+/// Anisotropy of `options` clamped to what the device support. Without
+/// support it is 1 and the sampler is trilinear, with a warning.
+fn anisotropy(options: SamplerOptions, max_anisotropy: u16) -> u16 {
+    let wanted = options.anisotropy.clamp(1, MAX_ANISOTROPY);
+    if wanted == 1 {
+        return 1;
+    }
+    if options.filter != FilterMode::Linear {
+        warn!(
+            "Anisotropy {} need Linear filter, not {:?}, disabled",
+            wanted, options.filter
+        );
+        return 1;
+    }
+    if max_anisotropy == 1 {
+        warn!(
+            "Anisotropic filtering is not supported, using trilinear instead of {}x",
+            wanted
+        );
+    }
+    wanted.min(max_anisotropy)
+}
+
+/// Sampled texture with the bind group used by textured pipelines.
+pub struct Texture {
+    texture: wgpu::Texture,
+    view: TextureView,
+    bind_group: BindGroup,
+}
+
+impl Texture {
+    /// Upload tightly packed sRGB RGBA8 `pixels` of `width` x `height`,
+    /// with every mip level down to 1x1 when `mipmaps` is set.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn from_rgba(
+        device: &Device,
+        queue: &Queue,
+        layout: &BindGroupLayout,
+        sampler: &Sampler,
+        width: u32,
+        height: u32,
+        pixels: &[u8],
+        mipmaps: bool,
+    ) -> Result<Self, String> {
+        if width == 0 || height == 0 {
+            return Err("Texture size can't be zero".to_string());
+        }
+        let expected = width as usize * height as usize * 4;
+        if pixels.len() != expected {
+            return Err(format!(
+                "Texture {}x{} need {} bytes of RGBA, got {}",
+                width,
+                height,
+                expected,
+                pixels.len()
+            ));
+        }
+
+        let mip_level_count = if mipmaps {
+            mip_level_count(width, height)
+        } else {
+            1
+        };
+        let data = mip_chain(width, height, pixels, mip_level_count);
+        let texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("rgba texture"),
+                size: wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            &data,
+        );
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = create_bind_group(device, layout, &view, sampler);
+
+        Ok(Texture {
+            texture,
+            view,
+            bind_group,
+        })
+    }
+
+    pub fn texture(&self) -> &wgpu::Texture {
+        &self.texture
+    }
+
+    pub fn view(&self) -> &TextureView {
+        &self.view
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.texture.width(), self.texture.height())
+    }
+
+    /// 1 without mipmaps, see `SamplerOptions::mipmaps`.
+    pub fn mip_level_count(&self) -> u32 {
+        self.texture.mip_level_count()
+    }
+
+    pub(crate) fn bind_group(&self) -> &BindGroup {
+        &self.bind_group
+    }
+}
+
+/// Levels of a full mip chain of `width` x `height`, halving the biggest
+/// side down to 1.
+pub(crate) fn mip_level_count(width: u32, height: u32) -> u32 {
+    u32::BITS - width.max(height).max(1).leading_zeros()
+}
+
+/// `pixels` followed by `levels - 1` smaller levels, each pixel the
+/// average of up to 2x2 pixels of the level above. Colors are averaged in
+/// linear space, averaging sRGB values would darken them.
+fn mip_chain(width: u32, height: u32, pixels: &[u8], levels: u32) -> Vec<u8> {
+    let mut data = pixels.to_vec();
+    let (mut w, mut h) = (width as usize, height as usize);
+    let mut level = pixels.to_vec();
+    for _ in 1..levels {
+        let (next_w, next_h) = ((w / 2).max(1), (h / 2).max(1));
+        let mut next = Vec::with_capacity(next_w * next_h * 4);
+        for y in 0..next_h {
+            for x in 0..next_w {
+                // odd sizes clamp to the last row and column
+                let xs = [(x * 2).min(w - 1), (x * 2 + 1).min(w - 1)];
+                let ys = [(y * 2).min(h - 1), (y * 2 + 1).min(h - 1)];
+                let mut sum = [0.0f32; 4];
+                for sy in ys {
+                    for sx in xs {
+                        let p = &level[(sy * w + sx) * 4..][..4];
+                        for (s, &v) in sum.iter_mut().zip(&p[..3]) {
+                            *s += srgb_to_linear(v);
+                        }
+                        sum[3] += p[3] as f32 / 255.0;
+                    }
+                }
+                for s in &sum[..3] {
+                    next.push(linear_to_srgb(s / 4.0));
+                }
+                next.push((sum[3] / 4.0 * 255.0).round() as u8);
+            }
+        }
+        data.extend_from_slice(&next);
+        (w, h, level) = (next_w, next_h, next);
+    }
+    data
+}
+
+fn srgb_to_linear(value: u8) -> f32 {
+    let x = value as f32 / 255.0;
+    if x <= 0.04045 {
+        x / 12.92
+    } else {
+        ((x + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> u8 {
+    let x = if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    };
+    (x.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Layout of a texture at binding 0 and its sampler at binding 1, shared by
+/// sprites and the post process pass.
+pub(crate) const TEXTURE_LAYOUT_ENTRIES: [BindGroupLayoutEntry; 2] = [
+    BindGroupLayoutEntry {
+        binding: 0,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+            view_dimension: wgpu::TextureViewDimension::D2,
+            multisampled: false,
+        },
+        count: None,
+    },
+    BindGroupLayoutEntry {
+        binding: 1,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+        count: None,
+    },
+];
+
+fn create_bind_group(
+    device: &Device,
+    layout: &BindGroupLayout,
+    view: &TextureView,
+    sampler: &Sampler,
+) -> BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("texture bind group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+        ],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mip_level_count_halves_biggest_side() {
+        assert_eq!(mip_level_count(256, 256), 9);
+        assert_eq!(mip_level_count(1, 1), 1);
+        assert_eq!(mip_level_count(300, 7), 9);
+    }
+
+    #[test]
+    fn mip_chain_odd_size_length() {
+        let (width, height) = (5, 3);
+        let pixels = vec![128; width * height * 4];
+        let levels = mip_level_count(width as u32, height as u32);
+        let data = mip_chain(width as u32, height as u32, &pixels, levels);
+        // 5x3, 2x1 then 1x1
+        let expected: usize = (0..levels)
+            .map(|i| (width >> i).max(1) * (height >> i).max(1) * 4)
+            .sum();
+        assert_eq!(expected, (15 + 2 + 1) * 4);
+        assert_eq!(data.len(), expected);
+    }
+}
@@ -0,0 +1,156 @@
+use std::{
+    cell::{Cell, RefCell},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use wgpu::{Buffer, CommandEncoder, Device, QuerySet, Queue, RenderPassTimestampWrites};
+
+/// Index of the scene pass for `GpuTimer::timestamp_writes`.
+pub(crate) const SCENE_PASS: u32 = 0;
+/// Index of the depth prepass, timed only when it is enabled.
+pub(crate) const DEPTH_PREPASS: u32 = 1;
+
+/// Measure render passes on the GPU with timestamp queries, a begin and
+/// an end timestamp per pass.
+///
+/// Results are read back without blocking, while the readback buffer is
+/// still mapping frames are not timed.
+pub(crate) struct GpuTimer {
+    query_set: QuerySet,
+    resolve_buffer: Buffer,
+    readback_buffer: Buffer,
+    passes: u32,
+    /// Queries written this frame, passes not recorded are not resolved.
+    written: Cell<u32>,
+    // nanosecond per timestamp tick
+    period: f32,
+    pending: Cell<bool>,
+    ready: Arc<AtomicBool>,
+    /// Duration of every pass of the last read frame.
+    last_ms: RefCell<Option<Vec<f32>>>,
+}
+
+impl GpuTimer {
+    /// Timer of up to `passes` passes per frame.
+    pub(crate) fn new(device: &Device, queue: &Queue, passes: u32) -> Self {
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("frame timestamps"),
+            ty: wgpu::QueryType::Timestamp,
+            count: passes * 2,
+        });
+        let buffer_size = Self::queries_size(passes * 2);
+        let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("timestamp resolve buffer"),
+            size: buffer_size,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("timestamp readback buffer"),
+            size: buffer_size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        GpuTimer {
+            query_set,
+            resolve_buffer,
+            readback_buffer,
+            passes,
+            written: Cell::new(0),
+            period: queue.get_timestamp_period(),
+            pending: Cell::new(false),
+            ready: Arc::new(AtomicBool::new(false)),
+            last_ms: RefCell::new(None),
+        }
+    }
+
+    fn queries_size(queries: u32) -> wgpu::BufferAddress {
+        queries as wgpu::BufferAddress * wgpu::QUERY_SIZE as wgpu::BufferAddress
+    }
+
+    /// Timestamp writes for pass `pass`, `None` while the previous result
+    /// is not read yet or when the pass is not timed. A pass recorded
+    /// without the passes of lower index is timed as zero.
+    pub(crate) fn timestamp_writes(&self, pass: u32) -> Option<RenderPassTimestampWrites<'_>> {
+        if self.pending.get() || pass >= self.passes {
+            return None;
+        }
+        self.written.set(self.written.get().max(pass * 2 + 2));
+        Some(RenderPassTimestampWrites {
+            query_set: &self.query_set,
+            beginning_of_pass_write_index: Some(pass * 2),
+            end_of_pass_write_index: Some(pass * 2 + 1),
+        })
+    }
+
+    /// Copy timestamps written this frame to the readback buffer.
+    pub(crate) fn resolve(&self, encoder: &mut CommandEncoder) {
+        let written = self.written.get();
+        if self.pending.get() || written == 0 {
+            return;
+        }
+        encoder.resolve_query_set(&self.query_set, 0..written, &self.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(
+            &self.resolve_buffer,
+            0,
+            &self.readback_buffer,
+            0,
+            Self::queries_size(written),
+        );
+    }
+
+    /// Start reading back timestamps, call after the frame is submitted.
+    pub(crate) fn map(&self) {
+        if self.pending.get() || self.written.get() == 0 {
+            return;
+        }
+        self.pending.set(true);
+        let ready = self.ready.clone();
+        self.readback_buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                if result.is_ok() {
+                    ready.store(true, Ordering::Release);
+                }
+            });
+    }
+
+    /// Read the result of a previous frame when the GPU is done with it.
+    pub(crate) fn poll(&self, device: &Device) {
+        if !self.pending.get() {
+            return;
+        }
+        device.poll(wgpu::Maintain::Poll);
+        if !self.ready.swap(false, Ordering::Acquire) {
+            return;
+        }
+        {
+            let data = self.readback_buffer.slice(..).get_mapped_range();
+            let timestamps: &[u64] = bytemuck::cast_slice(&data);
+            let durations = timestamps[..self.written.get() as usize]
+                .chunks_exact(2)
+                .map(|x| x[1].wrapping_sub(x[0]) as f32 * self.period / 1_000_000.0)
+                .collect();
+            *self.last_ms.borrow_mut() = Some(durations);
+        }
+        self.readback_buffer.unmap();
+        self.written.set(0);
+        self.pending.set(false);
+    }
+
+    /// Sum of every timed pass.
+    pub(crate) fn last_ms(&self) -> Option<f32> {
+        self.last_ms.borrow().as_ref().map(|x| x.iter().sum())
+    }
+
+    pub(crate) fn last_pass_ms(&self, pass: u32) -> Option<f32> {
+        self.last_ms
+            .borrow()
+            .as_ref()
+            .and_then(|x| x.get(pass as usize).copied())
+    }
+}
@@ -0,0 +1,233 @@
+use std::collections::HashSet;
+use std::ops::Range;
+
+use wgpu::{Buffer, Face, FrontFace, IndexFormat};
+
+use super::object::RenderObject;
+use super::{InstanceRaw, Vertex};
+
+/// Panic on debug build when `range` of `stride` bytes elements go past
+/// the end of `buffer`. wgpu would only report a validation error, often
+/// seen as a black screen, far from the code that set the range.
+#[track_caller]
+pub(crate) fn assert_draw_range(what: &str, range: &Range<u32>, buffer: &Buffer, stride: usize) {
+    if cfg!(debug_assertions) {
+        let count = buffer.size() / stride as u64;
+        assert!(
+            range.end as u64 <= count,
+            "{} range {:?} go past the end of its buffer, which hold {} elements",
+            what,
+            range,
+            count
+        );
+    }
+}
+
+/// `assert_draw_range` of the index and instance ranges of an object draw.
+#[track_caller]
+pub(crate) fn assert_object_draw(object: &RenderObject, instance_buffer: &Buffer, instances: u32) {
+    let index_size = match object.index_format {
+        IndexFormat::Uint16 => std::mem::size_of::<u16>(),
+        IndexFormat::Uint32 => std::mem::size_of::<u32>(),
+    };
+    assert_draw_range(
+        "Object index",
+        &(0..object.index_count),
+        &object.index_buffer,
+        index_size,
+    );
+    assert_draw_range(
+        "Instance",
+        &(0..instances),
+        instance_buffer,
+        std::mem::size_of::<InstanceRaw>(),
+    );
+}
+
+/// Triangles of a mesh sorted by how they are seen, see
+/// `XApp::check_winding`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WindingReport {
+    /// Triangles facing the camera with the builder front face.
+    pub front_facing: usize,
+    pub back_facing: usize,
+    /// Triangles with no area on screen or behind the camera, neither
+    /// front nor back facing.
+    pub degenerate: usize,
+    /// Triangles with an index past the last vertex.
+    pub out_of_range: usize,
+    /// Triangles the builder cull mode skip, front or back facing ones.
+    pub culled: usize,
+    /// Every edge is shared by two triangles going opposite ways, e.g. a
+    /// cube or a sphere. About half of a closed mesh always face away.
+    pub closed: bool,
+}
+
+impl WindingReport {
+    /// Whether culled triangles clearly outnumber the drawn ones on an
+    /// open mesh, it is then likely wound the wrong way.
+    pub fn is_mostly_culled(&self) -> bool {
+        let drawn = self.front_facing + self.back_facing - self.culled;
+        !self.closed && self.culled > 4 * drawn
+    }
+}
+
+/// Count how the `indices` triangle list of `vertices` is seen through
+/// column major `view_proj`.
+pub(crate) fn check_winding(
+    vertices: &[Vertex],
+    indices: &[u32],
+    view_proj: &[[f32; 4]; 4],
+    front_face: FrontFace,
+    cull_mode: Option<Face>,
+) -> WindingReport {
+    // screen position of a vertex, `None` behind the camera
+    let project = |v: &Vertex| {
+        let p = [v.position[0], v.position[1], v.position[2], 1.0];
+        let clip: [f32; 4] =
+            std::array::from_fn(|row| (0..4).map(|k| view_proj[k][row] * p[k]).sum());
+        (clip[3] > 0.0).then(|| [clip[0] / clip[3], clip[1] / clip[3]])
+    };
+    let mut report = WindingReport::default();
+    for triangle in indices.chunks_exact(3) {
+        let Some(corners) = triangle
+            .iter()
+            .map(|&i| vertices.get(i as usize))
+            .collect::<Option<Vec<_>>>()
+        else {
+            report.out_of_range += 1;
+            continue;
+        };
+        let Some([a, b, c]) = corners
+            .into_iter()
+            .map(project)
+            .collect::<Option<Vec<_>>>()
+            .and_then(|x| <[[f32; 2]; 3]>::try_from(x).ok())
+        else {
+            report.degenerate += 1;
+            continue;
+        };
+        // positive when counter clockwise on screen, y up in clip space
+        let area = (b[0] - a[0]) * (c[1] - a[1]) - (c[0] - a[0]) * (b[1] - a[1]);
+        if area.abs() <= f32::EPSILON {
+            report.degenerate += 1;
+            continue;
+        }
+        let front = (area > 0.0) == (front_face == FrontFace::Ccw);
+        if front {
+            report.front_facing += 1;
+        } else {
+            report.back_facing += 1;
+        }
+        if cull_mode == Some(if front { Face::Front } else { Face::Back }) {
+            report.culled += 1;
+        }
+    }
+    report.closed = is_closed(vertices, indices);
+    report
+}
+
+/// Whether every edge of the `indices` triangle list has its reverse in
+/// another triangle. Vertices are matched by position, so copies made for
+/// flat shading still close the mesh.
+fn is_closed(vertices: &[Vertex], indices: &[u32]) -> bool {
+    let position = |i: u32| {
+        vertices
+            .get(i as usize)
+            .map(|v| v.position.map(f32::to_bits))
+    };
+    let mut edges = HashSet::new();
+    for triangle in indices.chunks_exact(3) {
+        let Some([a, b, c]) = triangle
+            .iter()
+            .map(|&i| position(i))
+            .collect::<Option<Vec<_>>>()
+            .and_then(|x| <[[u32; 3]; 3]>::try_from(x).ok())
+        else {
+            continue;
+        };
+        edges.extend([(a, b), (b, c), (c, a)]);
+    }
+    !edges.is_empty() && edges.iter().all(|&(a, b)| edges.contains(&(b, a)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IDENTITY: [[f32; 4]; 4] = [
+        [1.0, 0.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ];
+
+    fn vertices(positions: &[[f32; 3]]) -> Vec<Vertex> {
+        positions
+            .iter()
+            .map(|&position| Vertex {
+                position,
+                color: [1.0; 3],
+                normal: [0.0, 0.0, -1.0],
+            })
+            .collect()
+    }
+
+    fn check(positions: &[[f32; 3]], indices: &[u32]) -> WindingReport {
+        check_winding(
+            &vertices(positions),
+            indices,
+            &IDENTITY,
+            FrontFace::Ccw,
+            Some(Face::Back),
+        )
+    }
+
+    // counter clockwise on screen, y up
+    const QUAD: [[f32; 3]; 4] = [
+        [-0.5, -0.5, 0.5],
+        [0.5, -0.5, 0.5],
+        [0.5, 0.5, 0.5],
+        [-0.5, 0.5, 0.5],
+    ];
+
+    #[test]
+    fn quad_wound_counter_clockwise_is_drawn() {
+        let report = check(&QUAD, &[0, 1, 2, 0, 2, 3]);
+        assert_eq!(report.front_facing, 2);
+        assert_eq!(report.culled, 0);
+        assert!(!report.closed);
+        assert!(!report.is_mostly_culled());
+    }
+
+    #[test]
+    fn reversed_quad_is_mostly_culled() {
+        let report = check(&QUAD, &[0, 2, 1, 0, 3, 2]);
+        assert_eq!(report.back_facing, 2);
+        assert_eq!(report.culled, 2);
+        assert!(report.is_mostly_culled());
+    }
+
+    #[test]
+    fn closed_cube_does_not_warn() {
+        // corner i has coordinate bit j set to +0.5
+        let corners: Vec<[f32; 3]> = (0..8)
+            .map(|i| std::array::from_fn(|j| if i >> j & 1 == 1 { 0.5 } else { -0.5 }))
+            .collect();
+        #[rustfmt::skip]
+        let indices = [
+            0, 2, 3, 0, 3, 1, // -z
+            4, 5, 7, 4, 7, 6, // +z
+            0, 4, 6, 0, 6, 2, // -x
+            1, 3, 7, 1, 7, 5, // +x
+            0, 1, 5, 0, 5, 4, // -y
+            2, 6, 7, 2, 7, 3, // +y
+        ];
+        let report = check(&corners, &indices);
+        assert!(report.closed);
+        assert!(report.culled > 0);
+        assert!(!report.is_mostly_culled());
+        // a single face of it is open
+        assert!(!check(&corners, &indices[..6]).closed);
+    }
+}
@@ -0,0 +1,199 @@
+use std::path::PathBuf;
+
+use log::warn;
+use sdl2::video::{FullscreenType, Window};
+
+use super::XAppBuilder;
+
+const FILE_NAME: &str = "window.json";
+
+/// Window placement saved on quit when
+/// `XAppBuilder::remember_window_state` is set.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct WindowState {
+    display: i32,
+    /// Top left corner relative to the display.
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    fullscreen: bool,
+}
+
+impl WindowState {
+    /// Read placement of `window`, `None` when SDL can't tell its display.
+    pub(crate) fn from_window(window: &Window) -> Option<Self> {
+        let display = window.display_index().ok()?;
+        let bounds = window.subsystem().display_bounds(display).ok()?;
+        let (x, y) = window.position();
+        let (width, height) = window.size();
+        Some(WindowState {
+            display,
+            x: x - bounds.x(),
+            y: y - bounds.y(),
+            width,
+            height,
+            fullscreen: window.fullscreen_state() != FullscreenType::Off,
+        })
+    }
+
+    /// Use saved placement as `builder` display, position and size.
+    pub(crate) fn apply(&self, mut builder: XAppBuilder) -> XAppBuilder {
+        builder.display = self.display;
+        if self.fullscreen {
+            builder.windowed = None;
+            builder.position = None;
+        } else {
+            builder.windowed = Some((self.width, self.height));
+            builder.position = Some((self.x, self.y));
+        }
+        builder
+    }
+
+    /// Load state saved for app `title`, the builder title. Missing or corrupt file give
+    /// `None` so builder settings are used.
+    pub(crate) fn load(title: &str) -> Option<Self> {
+        let path = state_path(title)?;
+        let text = std::fs::read_to_string(&path).ok()?;
+        let state = Self::parse(&text);
+        if state.is_none() {
+            warn!("Ignoring corrupt window state {}", path.display());
+        }
+        state
+    }
+
+    /// Save state for app `title`, failure is only logged.
+    pub(crate) fn save(&self, title: &str) {
+        let Some(path) = state_path(title) else {
+            return;
+        };
+        if let Err(e) = std::fs::write(&path, self.to_json()) {
+            warn!("Cannot save window state {}: {}", path.display(), e);
+        }
+    }
+
+    fn to_json(self) -> String {
+        format!(
+            "{{\"display\":{},\"x\":{},\"y\":{},\"width\":{},\"height\":{},\"fullscreen\":{}}}\n",
+            self.display, self.x, self.y, self.width, self.height, self.fullscreen
+        )
+    }
+
+    /// Parse the flat object written by `to_json`, keys in any order.
+    fn parse(text: &str) -> Option<Self> {
+        let body = text.trim().strip_prefix('{')?.strip_suffix('}')?;
+        let (mut display, mut x, mut y) = (None, None, None);
+        let (mut width, mut height, mut fullscreen) = (None, None, None);
+        for field in body.split(',') {
+            let (key, value) = field.split_once(':')?;
+            let value = value.trim();
+            match key.trim().trim_matches('"') {
+                "display" => display = value.parse().ok(),
+                "x" => x = value.parse().ok(),
+                "y" => y = value.parse().ok(),
+                "width" => width = value.parse().ok(),
+                "height" => height = value.parse().ok(),
+                "fullscreen" => fullscreen = value.parse().ok(),
+                _ => {}
+            }
+        }
+        let state = WindowState {
+            display: display?,
+            x: x?,
+            y: y?,
+            width: width?,
+            height: height?,
+            fullscreen: fullscreen?,
+        };
+        (state.width > 0 && state.height > 0).then_some(state)
+    }
+}
+
+/// `window.json` in SDL per-user preference directory of the app.
+fn state_path(title: &str) -> Option<PathBuf> {
+    match sdl2::filesystem::pref_path("wgpu_game", &directory_name(title)) {
+        Ok(dir) => Some(PathBuf::from(dir).join(FILE_NAME)),
+        Err(e) => {
+            warn!("No preference directory for window state: {}", e);
+            None
+        }
+    }
+}
+
+/// `title` usable as a directory name, characters other than ASCII
+/// letters, digits, `-` and `_` are replaced by `_`.
+fn directory_name(title: &str) -> String {
+    let name: String = title
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.is_empty() {
+        "app".to_string()
+    } else {
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATE: WindowState = WindowState {
+        display: 1,
+        x: -20,
+        y: 40,
+        width: 800,
+        height: 600,
+        fullscreen: false,
+    };
+
+    #[test]
+    fn json_round_trip() {
+        assert_eq!(WindowState::parse(&STATE.to_json()), Some(STATE));
+        let fullscreen = WindowState {
+            fullscreen: true,
+            ..STATE
+        };
+        assert_eq!(WindowState::parse(&fullscreen.to_json()), Some(fullscreen));
+    }
+
+    #[test]
+    fn parse_any_key_order_and_spacing() {
+        let text = r#" { "fullscreen": false, "height": 600, "width": 800,
+            "y": 40, "x": -20, "display": 1, "extra": 0 } "#;
+        assert_eq!(WindowState::parse(text), Some(STATE));
+    }
+
+    #[test]
+    fn parse_corrupt_input() {
+        for text in [
+            "",
+            "not json",
+            "{\"display\":1,\"x\":-20",
+            "{\"display\":one,\"x\":-20,\"y\":40,\"width\":800,\"height\":600,\"fullscreen\":false}",
+            "{\"display\":1,\"x\":-20,\"y\":40,\"width\":800,\"height\":600,\"fullscreen\":maybe}",
+            "{\"display\":1,\"x\":-20,\"y\":40,\"width\":0,\"height\":600,\"fullscreen\":false}",
+        ] {
+            assert_eq!(WindowState::parse(text), None, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn parse_missing_field() {
+        let text = "{\"display\":1,\"x\":-20,\"y\":40,\"width\":800,\"fullscreen\":false}";
+        assert_eq!(WindowState::parse(text), None);
+    }
+
+    #[test]
+    fn title_directory_name() {
+        assert_eq!(directory_name("My Game: v1/2"), "My_Game__v1_2");
+        assert_eq!(directory_name("wgpu-game_2"), "wgpu-game_2");
+        assert_eq!(directory_name(""), "app");
+    }
+}
@@ -2,11 +2,11 @@ use app::XApp;
 
 use sdl2::{libc, log::log};
 
-mod app;
+pub mod app;
 
 #[no_mangle]
 pub extern "C" fn SDL_main(_argc: libc::c_int, _argv: *const *const libc::c_char) -> libc::c_int {
-    let game = match XApp::new("WGPU Game") {
+    let mut game = match XApp::new("WGPU Game") {
         Ok(x) => x,
         Err(e) => {
             log(&format!("Error on init XApp: {}", e));
@@ -14,10 +14,12 @@ pub extern "C" fn SDL_main(_argc: libc::c_int, _argv: *const *const libc::c_char
         }
     };
 
-    if let Some(e) = game.run().err() {
+    if let Some(e) = game.run(&mut ()).err() {
         log(&format!("Error on run XApp: {}", e));
         return 2;
     }
 
+    game.shutdown();
+
     0
 }
@@ -1,12 +1,10 @@
 use std::process::exit;
 
-use app::XApp;
-
 use sdl2::log::log;
-mod app;
+use wgpu_game::app::XApp;
 
 fn main() {
-    let game = match XApp::new("WGPU Game") {
+    let mut game = match XApp::new("WGPU Game") {
         Ok(x) => x,
         Err(e) => {
             log(&format!("Error on init XApp: {}", e));
@@ -14,8 +12,10 @@ fn main() {
         }
     };
 
-    if let Some(e) = game.run().err() {
+    if let Some(e) = game.run(&mut ()).err() {
         log(&format!("Error on run XApp: {}", e));
         exit(2);
     }
+
+    game.shutdown();
 }